@@ -0,0 +1,73 @@
+use crate::config;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+/// An entry in one of the bundled databases that can be looked up by name.
+pub trait Named {
+    /// The maker, the model and any aliases of the entry.
+    fn names(&self) -> (&str, &str, &[String]);
+}
+
+/// Returns the path of the user's extension of a bundled database, if there is one.
+pub fn user_database(file_name: &str) -> Option<PathBuf> {
+    config::config_dir()
+        .map(|dir| dir.join(file_name))
+        .filter(|path| path.is_file())
+}
+
+/// Matches the name against the key, the aliases and the full name of each entry.
+/// Names are compared case-insensitively while ignoring spaces, dashes and underscores.
+pub fn find<'a, T: Named>(entries: &'a BTreeMap<String, T>, name: &str) -> Option<&'a T> {
+    let name = normalize(name);
+    entries
+        .iter()
+        .find(|(key, entry)| candidates(key, *entry).any(|candidate| candidate == name))
+        .map(|(_, entry)| entry)
+}
+
+/// Finds the key of the entry with a name closest to the given one, for suggesting corrections.
+pub fn suggest<'a, T: Named>(entries: &'a BTreeMap<String, T>, name: &str) -> Option<&'a str> {
+    let name = normalize(name);
+    entries
+        .iter()
+        .filter_map(|(key, entry)| {
+            let distance = candidates(key, entry)
+                .map(|candidate| edit_distance(&candidate, &name))
+                .min()?;
+            Some((distance, key))
+        })
+        .filter(|(distance, _)| *distance <= name.len() / 3 + 1)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, key)| key.as_str())
+}
+
+fn candidates<T: Named>(key: &str, entry: &T) -> impl Iterator<Item = String> {
+    let (make, model, aliases) = entry.names();
+    [normalize(key), normalize(&format!("{make} {model}"))]
+        .into_iter()
+        .chain(aliases.iter().map(|alias| normalize(alias)))
+}
+
+/// Normalizes a name for comparison by ignoring case, whitespace, dashes and underscores.
+pub fn normalize(name: &str) -> String {
+    name.chars()
+        .filter(|c| !c.is_whitespace() && *c != '-' && *c != '_')
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, a) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a != *b);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+
+    previous[b.len()]
+}
@@ -0,0 +1,75 @@
+use crate::summary::Summary;
+use anyhow::{Result, anyhow};
+use clap::ValueEnum;
+use std::path::PathBuf;
+
+/// A piece of metadata that a scan can be required to have.
+#[derive(Clone, Copy, ValueEnum)]
+pub enum Field {
+    Film,
+    Iso,
+    Camera,
+    Lens,
+    FocalLength,
+    Aperture,
+    Shutter,
+    Date,
+    Artist,
+    Copyright,
+    Location,
+}
+
+impl Field {
+    /// The fields that are required when none are given explicitly.
+    pub const DEFAULT: [Self; 4] = [Self::Film, Self::Iso, Self::Camera, Self::Date];
+
+    fn is_set(self, summary: &Summary) -> bool {
+        match self {
+            Self::Film => summary.film.is_some(),
+            Self::Iso => summary.iso.is_some(),
+            Self::Camera => summary.camera.is_some(),
+            Self::Lens => summary.lens.is_some(),
+            Self::FocalLength => summary.focal_length.is_some(),
+            Self::Aperture => summary.aperture.is_some(),
+            Self::Shutter => summary.shutter.is_some(),
+            Self::Date => summary.captured.is_some(),
+            Self::Artist => summary.artist.is_some(),
+            Self::Copyright => summary.copyright.is_some(),
+            Self::Location => summary.coordinates.is_some() || summary.location.is_some(),
+        }
+    }
+
+    fn name(self) -> String {
+        self.to_possible_value()
+            .map(|value| value.get_name().to_string())
+            .unwrap_or_default()
+    }
+}
+
+/// Prints the files that are missing any of the required fields,
+/// and fails if there are any so that it can be used in scripts.
+pub fn check(files: &[PathBuf], required: &[Field]) -> Result<()> {
+    let mut incomplete = 0;
+    for file in files {
+        let summary = Summary::read(file)?;
+        let missing: Vec<String> = required
+            .iter()
+            .filter(|field| !field.is_set(&summary))
+            .map(|field| field.name())
+            .collect();
+
+        if !missing.is_empty() {
+            println!("{}: missing {}", file.display(), missing.join(", "));
+            incomplete += 1;
+        }
+    }
+
+    if incomplete > 0 {
+        return Err(anyhow!(
+            "{incomplete} of {} files are missing required metadata",
+            files.len()
+        ));
+    }
+
+    Ok(())
+}
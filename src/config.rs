@@ -0,0 +1,59 @@
+use crate::profile::Tags;
+use anyhow::{Context, Result};
+use std::env::{self, VarError};
+use std::path::PathBuf;
+use std::str::FromStr;
+
+/// The directory holding user-level configuration, usually `~/.config/rolltag`.
+pub fn config_dir() -> Option<PathBuf> {
+    env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::home_dir().map(|home| home.join(".config")))
+        .map(|dir| dir.join("rolltag"))
+}
+
+/// Loads the user-level defaults. Values from `ROLLTAG_*` environment variables
+/// take precedence over the ones in `config.toml`, which uses the same keys as roll profiles.
+pub fn user_defaults() -> Result<Tags> {
+    let file = match config_dir().map(|dir| dir.join("config.toml")) {
+        Some(path) if path.is_file() => Tags::from_path(&path)?,
+        _ => Tags::default(),
+    };
+
+    Ok(env_defaults()?.or(file))
+}
+
+fn env_defaults() -> Result<Tags> {
+    Ok(Tags {
+        film: var("ROLLTAG_FILM")?,
+        format: var("ROLLTAG_FORMAT")?,
+        iso: var("ROLLTAG_ISO")?,
+        camera: var("ROLLTAG_CAMERA")?,
+        lens: var("ROLLTAG_LENS")?,
+        developer: var("ROLLTAG_DEVELOPER")?,
+        artist: var("ROLLTAG_ARTIST")?,
+        copyright: var("ROLLTAG_COPYRIGHT")?,
+        license: var("ROLLTAG_LICENSE")?,
+        focal_length: var("ROLLTAG_FOCAL_LENGTH")?,
+        tz: var("ROLLTAG_TZ")?,
+        ..Tags::default()
+    })
+}
+
+fn var<T>(name: &str) -> Result<Option<T>>
+where
+    T: FromStr,
+    T::Err: Into<anyhow::Error>,
+{
+    match env::var(name) {
+        Ok(value) if value.is_empty() => Ok(None),
+        Ok(value) => value
+            .parse()
+            .map(Some)
+            .map_err(Into::into)
+            .with_context(|| format!("Invalid value for {name}: {value}")),
+        Err(VarError::NotPresent) => Ok(None),
+        Err(err) => Err(err).with_context(|| format!("Invalid value for {name}")),
+    }
+}
@@ -0,0 +1,266 @@
+use crate::de::deserialize_from_str;
+use anyhow::{Error, Result, anyhow};
+use serde::{Deserialize, Deserializer};
+use std::str::FromStr;
+use time::format_description::FormatItem;
+use time::{Date, Duration, PrimitiveDateTime, Time, macros::format_description};
+
+/// A date, optionally with a time of day, given as a flag or in a roll profile.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DateTime(pub PrimitiveDateTime);
+
+impl FromStr for DateTime {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        parse_date_time(s).map(DateTime)
+    }
+}
+
+impl<'de> Deserialize<'de> for DateTime {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        // Dates may be written both as strings and as native TOML dates.
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Raw {
+            Text(String),
+            Toml(toml::value::Datetime),
+        }
+
+        let text = match Raw::deserialize(deserializer)? {
+            Raw::Text(text) => text,
+            Raw::Toml(date) => date.to_string(),
+        };
+        text.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// A length of time, like the time between two consecutive frames.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Interval(pub Duration);
+
+impl Default for Interval {
+    fn default() -> Self {
+        Self(Duration::MINUTE)
+    }
+}
+
+impl FromStr for Interval {
+    type Err = Error;
+
+    /// Parses intervals written as `30s`, `5m`, `2h` or `1d`, where a plain number is in seconds.
+    /// Intervals may be negative, like `-1h`.
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        let (sign, unsigned) = match s.strip_prefix('-') {
+            Some(unsigned) => (-1, unsigned),
+            None => (1, s.strip_prefix('+').unwrap_or(s)),
+        };
+        let split = unsigned
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(unsigned.len());
+        let (number, unit) = unsigned.split_at(split);
+        let number: i64 = number
+            .parse()
+            .map_err(|_| anyhow!("Invalid interval: {s}"))?;
+        let scale = match unit.trim() {
+            "" | "s" => 1,
+            "m" | "min" => 60,
+            "h" => 60 * 60,
+            "d" => 24 * 60 * 60,
+            _ => return Err(anyhow!("Invalid interval: {s}")),
+        };
+        let seconds = number
+            .checked_mul(scale * sign)
+            .ok_or_else(|| anyhow!("Invalid interval: {s}"))?;
+        Ok(Self(Duration::seconds(seconds)))
+    }
+}
+
+deserialize_from_str!(Interval);
+
+/// A span of time that the frames of a roll were shot over.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DateRange {
+    pub start: PrimitiveDateTime,
+    pub end: PrimitiveDateTime,
+}
+
+impl DateRange {
+    /// The time of a frame when spreading all frames evenly over the range.
+    pub fn spread(self, index: usize, count: usize) -> PrimitiveDateTime {
+        let span = i128::from((self.end - self.start).whole_seconds());
+        let steps = i128::try_from(count.saturating_sub(1))
+            .unwrap_or(i128::MAX)
+            .max(1);
+        let index = i128::try_from(index).unwrap_or(i128::MAX);
+        let offset = i64::try_from(span * index / steps).unwrap_or(i64::MAX);
+        self.start.saturating_add(Duration::seconds(offset))
+    }
+}
+
+impl FromStr for DateRange {
+    type Err = Error;
+
+    /// Parses ranges written as `START..END` using any of the accepted date formats.
+    /// An end date without a time of day includes the whole day, up to `23:59:59`.
+    fn from_str(s: &str) -> Result<Self> {
+        let (start, end) = s
+            .split_once("..")
+            .ok_or_else(|| anyhow!("Invalid date range, expected START..END: {s}"))?;
+        let start = parse_date_time(start)?;
+        let (date, time) = parse_date_and_time(end)?;
+        let end = date.with_time(time.unwrap_or(END_OF_DAY));
+        if end < start {
+            return Err(anyhow!("The date range ends before it starts: {s}"));
+        }
+        Ok(Self { start, end })
+    }
+}
+
+deserialize_from_str!(DateRange);
+
+const END_OF_DAY: Time = time::macros::time!(23:59:59);
+
+/// Accepted ways of writing a date, tried in order.
+const DATE_FORMATS: &[&[FormatItem<'_>]] = &[
+    format_description!("[year]-[month]-[day]"),
+    format_description!("[year]:[month]:[day]"),
+    format_description!("[year]/[month]/[day]"),
+    format_description!("[year][month][day]"),
+    format_description!("[day padding:none].[month padding:none].[year]"),
+    format_description!("[day padding:none] [month repr:short case_sensitive:false] [year]"),
+    format_description!("[day padding:none] [month repr:long case_sensitive:false] [year]"),
+];
+
+/// Accepted ways of writing a time of day, tried in order.
+const TIME_FORMATS: &[&[FormatItem<'_>]] = &[
+    format_description!("[hour padding:none]:[minute]:[second]"),
+    format_description!("[hour padding:none]:[minute]"),
+];
+
+/// Parses a date optionally followed by a time of day, separated by a space or a `T`.
+/// Dates may be written like `2024-05-12`, `2024:05:12`, `2024/05/12`, `20240512`,
+/// `12.05.2024`, `12 May 2024` or `12 September 2024`, and times like `14:30` or `14:30:15`.
+/// Dates without a time of day are placed at midnight.
+pub fn parse_date_time(s: &str) -> Result<PrimitiveDateTime> {
+    let (date, time) = parse_date_and_time(s)?;
+    Ok(date.with_time(time.unwrap_or(Time::MIDNIGHT)))
+}
+
+fn parse_date_and_time(s: &str) -> Result<(Date, Option<Time>)> {
+    let s = s.trim();
+    let split = s.rsplit_once([' ', 'T']).and_then(|(date, time)| {
+        let time = TIME_FORMATS
+            .iter()
+            .find_map(|format| Time::parse(time, format).ok())?;
+        Some((date.trim_end(), Some(time)))
+    });
+    let (date, time) = split.unwrap_or((s, None));
+
+    DATE_FORMATS
+        .iter()
+        .find_map(|format| Date::parse(date, format).ok())
+        .map(|date| (date, time))
+        .ok_or_else(|| anyhow!("Invalid date: {s}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::datetime;
+
+    #[test]
+    fn parses_date_formats() {
+        for date in [
+            "2024-05-12",
+            "2024:05:12",
+            "2024/05/12",
+            "20240512",
+            "12.05.2024",
+            "12.5.2024",
+            "12 May 2024",
+            "12 may 2024",
+            "12 MAY 2024",
+        ] {
+            assert_eq!(
+                parse_date_time(date).unwrap(),
+                datetime!(2024-05-12 00:00),
+                "{date}"
+            );
+        }
+        assert_eq!(
+            parse_date_time("12 September 2024").unwrap(),
+            datetime!(2024-09-12 00:00)
+        );
+    }
+
+    #[test]
+    fn parses_times_of_day() {
+        assert_eq!(
+            parse_date_time("2024-05-12 14:30").unwrap(),
+            datetime!(2024-05-12 14:30)
+        );
+        assert_eq!(
+            parse_date_time("2024-05-12T14:30:15").unwrap(),
+            datetime!(2024-05-12 14:30:15)
+        );
+        assert_eq!(
+            parse_date_time("12 May 2024 9:05").unwrap(),
+            datetime!(2024-05-12 09:05)
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_dates() {
+        for date in [
+            "",
+            "2024-13-01",
+            "2024-05-12 25:00",
+            "yesterday",
+            "12/05/2024",
+        ] {
+            assert!(parse_date_time(date).is_err(), "{date}");
+        }
+    }
+
+    #[test]
+    fn parses_intervals() {
+        let interval = |s: &str| s.parse::<Interval>().unwrap().0;
+        assert_eq!(interval("30"), Duration::seconds(30));
+        assert_eq!(interval("30s"), Duration::seconds(30));
+        assert_eq!(interval("5m"), Duration::minutes(5));
+        assert_eq!(interval("5min"), Duration::minutes(5));
+        assert_eq!(interval("2h"), Duration::hours(2));
+        assert_eq!(interval("1d"), Duration::days(1));
+        assert_eq!(interval("-1h"), Duration::hours(-1));
+        assert_eq!(interval("+90s"), Duration::seconds(90));
+    }
+
+    #[test]
+    fn rejects_invalid_intervals() {
+        for interval in [
+            "",
+            "h",
+            "5w",
+            "1.5h",
+            "--1h",
+            "999999999999999999m",
+            "-999999999999999999d",
+            "99999999999999999999",
+        ] {
+            assert!(interval.parse::<Interval>().is_err(), "{interval}");
+        }
+    }
+
+    #[test]
+    fn includes_the_whole_end_date_in_ranges() {
+        let range: DateRange = "2024-05-01..2024-05-20".parse().unwrap();
+        assert_eq!(range.start, datetime!(2024-05-01 00:00));
+        assert_eq!(range.end, datetime!(2024-05-20 23:59:59));
+
+        let range: DateRange = "2024-05-01 08:00..2024-05-20 18:00".parse().unwrap();
+        assert_eq!(range.end, datetime!(2024-05-20 18:00));
+        assert!("2024-05-20..2024-05-01".parse::<DateRange>().is_err());
+    }
+}
@@ -0,0 +1,45 @@
+/// Implements `Deserialize` for types that are written as strings in profiles and shot logs,
+/// parsing them with their `FromStr` implementation so that both report the same errors.
+macro_rules! deserialize_from_str {
+    ($($type:ty),+ $(,)?) => {$(
+        impl<'de> serde::Deserialize<'de> for $type {
+            fn deserialize<D: serde::Deserializer<'de>>(
+                deserializer: D,
+            ) -> std::result::Result<Self, D::Error> {
+                <String as serde::Deserialize>::deserialize(deserializer)?
+                    .parse()
+                    .map_err(serde::de::Error::custom)
+            }
+        }
+    )+};
+}
+
+pub(crate) use deserialize_from_str;
+
+#[cfg(test)]
+mod tests {
+    use crate::exposure::{Aperture, ExposureTime};
+    use num_rational::Ratio;
+    use serde::Deserialize;
+
+    #[derive(Deserialize)]
+    struct Exposure {
+        aperture: Aperture,
+        shutter: ExposureTime,
+    }
+
+    #[test]
+    fn deserializes_with_from_str() {
+        let exposure: Exposure = toml::from_str("aperture = \"f/8\"\nshutter = \"1/125\"").unwrap();
+        assert_eq!(exposure.aperture.0, Ratio::from_integer(8));
+        assert_eq!(exposure.shutter.0, Ratio::new(1, 125));
+
+        let error = toml::from_str::<Exposure>("aperture = \"f/0\"\nshutter = \"1/125\"")
+            .err()
+            .unwrap();
+        assert!(
+            error.to_string().contains("Invalid aperture: f/0"),
+            "{error}"
+        );
+    }
+}
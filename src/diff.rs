@@ -0,0 +1,142 @@
+use anyhow::Result;
+use rexiv2::Metadata;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::path::Path;
+
+/// The Exif groups whose tags are written back exactly from their string values.
+/// Maker notes and thumbnails are stored in their own groups and laid out by Exiv2 when saving.
+const REVERTIBLE_GROUPS: &[&str] = &["Image", "Photo", "GPSInfo", "Iop"];
+
+/// Binary Exif tags in the groups above, whose string values do not round-trip.
+const BINARY_TAGS: &[&str] = &[
+    "Exif.Photo.MakerNote",
+    "Exif.Image.InterColorProfile",
+    "Exif.Image.IPTCNAA",
+    "Exif.Image.ImageResources",
+    "Exif.Image.PrintImageMatching",
+    "Exif.Image.XMLPacket",
+    "Exif.Image.DNGPrivateData",
+];
+
+/// Whether a tag can be set back to an earlier value when undoing a change.
+pub fn revertible(tag: &str) -> bool {
+    match tag.strip_prefix("Exif.") {
+        Some(rest) => {
+            let group = rest.split('.').next().unwrap_or_default();
+            REVERTIBLE_GROUPS.contains(&group) && !BINARY_TAGS.contains(&tag)
+        }
+        None => true,
+    }
+}
+
+/// The values of all tags in an image at one point in time.
+/// XMP arrays and repeated IPTC tags have one entry per value.
+pub type Snapshot = BTreeMap<String, Vec<String>>;
+
+/// A tag that was added, changed or removed.
+#[derive(Serialize, Deserialize)]
+pub struct Change {
+    pub tag: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub before: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub after: Option<Vec<String>>,
+}
+
+impl Change {
+    /// Sets the tag back to the value it had before the change, or removes it if it was added.
+    pub fn revert(&self, meta: &Metadata) -> Result<()> {
+        match &self.before {
+            None => {
+                meta.clear_tag(&self.tag);
+            }
+            // Single values are set as strings, which keeps the type that Exiv2 knows the tag to have.
+            Some(values) if values.len() == 1 => {
+                meta.set_tag_string(&self.tag, &values[0])?;
+            }
+            Some(values) => {
+                let values: Vec<&str> = values.iter().map(String::as_str).collect();
+                meta.set_tag_multiple_strings(&self.tag, &values)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Reads the value of every Exif, XMP and IPTC tag in the image.
+pub fn snapshot(meta: &Metadata) -> Snapshot {
+    let exif = meta
+        .get_exif_tags()
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|tag| {
+            let value = meta.get_tag_string(&tag).ok()?;
+            Some((tag, vec![value]))
+        });
+
+    let xmp = meta.get_xmp_tags().unwrap_or_default();
+    let iptc = meta.get_iptc_tags().unwrap_or_default();
+    let multiple = xmp.into_iter().chain(iptc).filter_map(|tag| {
+        let values = meta.get_tag_multiple_strings(&tag).ok()?;
+        Some((tag, values))
+    });
+
+    exif.chain(multiple).collect()
+}
+
+/// Finds the tags that differ between the two snapshots, in tag order.
+pub fn changes(before: &Snapshot, after: &Snapshot) -> Vec<Change> {
+    let mut tags: Vec<&String> = before.keys().chain(after.keys()).collect();
+    tags.sort();
+    tags.dedup();
+
+    tags.into_iter()
+        .filter(|tag| before.get(*tag) != after.get(*tag))
+        .map(|tag| Change {
+            tag: tag.clone(),
+            before: before.get(tag).cloned(),
+            after: after.get(tag).cloned(),
+        })
+        .collect()
+}
+
+/// Describes the changes to a file with one line per tag, marking added tags
+/// with `+`, removed tags with `-` and changed tags with `~`.
+pub fn render(file: &Path, changes: &[Change]) -> String {
+    if changes.is_empty() {
+        return format!("{}: no changes\n", file.display());
+    }
+
+    let mut out = format!("{}\n", file.display());
+    for change in changes {
+        let line = match (&change.before, &change.after) {
+            (None, Some(after)) => format!("+ {}: {}", change.tag, shorten(after)),
+            (Some(before), None) => format!("- {}: {}", change.tag, shorten(before)),
+            (Some(before), Some(after)) => {
+                format!(
+                    "~ {}: {} -> {}",
+                    change.tag,
+                    shorten(before),
+                    shorten(after)
+                )
+            }
+            (None, None) => continue,
+        };
+        let _ = writeln!(out, "  {line}");
+    }
+    out
+}
+
+// Binary tags can hold thousands of values, which would drown out everything else.
+fn shorten(values: &[String]) -> String {
+    const MAX_CHARS: usize = 60;
+    let value = values.join("; ");
+    if value.chars().count() <= MAX_CHARS {
+        return value;
+    }
+
+    let short: String = value.chars().take(MAX_CHARS).collect();
+    format!("{short}…")
+}
@@ -0,0 +1,86 @@
+use crate::exposure::parse_rational;
+use crate::paths::file_name;
+use crate::sequence;
+use crate::summary::Summary;
+use anyhow::{Context, Result};
+use num_rational::Ratio;
+use serde::Serialize;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// A row in the same format as the shot logs that are read with `--shot-log`.
+#[derive(Serialize)]
+struct Row {
+    frame: usize,
+    file: String,
+    aperture: Option<String>,
+    shutter: Option<String>,
+    date: Option<String>,
+    notes: Option<String>,
+    location: Option<String>,
+}
+
+/// Writes the per-frame metadata of the files as a shot log, to the output file or else to stdout.
+/// Frames are numbered in filename order within each directory.
+pub fn export(files: &[PathBuf], output: Option<&Path>) -> Result<()> {
+    let out: Box<dyn io::Write> = match output {
+        Some(path) => Box::new(
+            std::fs::File::create(path)
+                .with_context(|| format!("Failed to create {}", path.display()))?,
+        ),
+        None => Box::new(io::stdout().lock()),
+    };
+    let mut writer = csv::Writer::from_writer(out);
+
+    for (file, position) in files.iter().zip(sequence::positions(files)) {
+        let summary = Summary::read(file)?;
+        // Coordinates are preferred over the place name since they can be written back as a location.
+        let location = summary
+            .coordinates
+            .map(|coordinates| coordinates.to_string())
+            .or(summary.location);
+
+        writer.serialize(Row {
+            frame: position.index + 1,
+            file: file_name(file),
+            aperture: summary.aperture.map(exact),
+            shutter: summary.shutter.map(|shutter| shutter.to_string()),
+            date: summary.captured,
+            notes: summary.notes,
+            location,
+        })?;
+    }
+
+    writer.flush()?;
+    Ok(())
+}
+
+/// Formats a rational exactly, as a decimal number like `5.6` or `0.95` when it reads back as the
+/// same value, or else as a fraction.
+fn exact(value: Ratio<i32>) -> String {
+    let decimal = (f64::from(*value.numer()) / f64::from(*value.denom())).to_string();
+    if parse_rational(&decimal) == Some(value) {
+        decimal
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writes_apertures_exactly() {
+        for (value, text) in [
+            (Ratio::from_integer(8), "8"),
+            (Ratio::new(56, 10), "5.6"),
+            (Ratio::new(95, 100), "0.95"),
+            (Ratio::new(12, 10), "1.2"),
+            (Ratio::new(1, 3), "1/3"),
+        ] {
+            assert_eq!(exact(value), text);
+            assert_eq!(parse_rational(text), Some(value));
+        }
+    }
+}
@@ -0,0 +1,164 @@
+use crate::de::deserialize_from_str;
+use anyhow::{Error, Result, anyhow};
+use num_rational::Ratio;
+use rexiv2::Metadata;
+use std::str::FromStr;
+
+/// The f-number that a frame was exposed at.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Aperture(pub Ratio<i32>);
+
+impl Aperture {
+    /// Writes the f-number together with its APEX value, which is `2 * log2(N)`.
+    pub fn write(self, meta: &Metadata) -> Result<()> {
+        meta.set_tag_rational("Exif.Photo.FNumber", &self.0)?;
+        // The APEX value is unsigned, so apertures faster than f/1 like f/0.95 are written as zero.
+        let apex = (2.0 * to_f64(self.0).log2()).max(0.0);
+        meta.set_tag_rational("Exif.Photo.ApertureValue", &hundredths(apex))?;
+        Ok(())
+    }
+}
+
+impl FromStr for Aperture {
+    type Err = Error;
+
+    /// Parses apertures written as `f/8`, `f5.6` or just `11`.
+    fn from_str(s: &str) -> Result<Self> {
+        let number = s
+            .trim()
+            .trim_start_matches(['f', 'F'])
+            .trim_start_matches('/');
+        parse_rational(number)
+            .filter(|n| *n > Ratio::from_integer(0))
+            .map(Aperture)
+            .ok_or_else(|| anyhow!("Invalid aperture: {s}"))
+    }
+}
+
+/// The time in seconds that a frame was exposed for.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ExposureTime(pub Ratio<i32>);
+
+impl ExposureTime {
+    /// Writes the exposure time together with its APEX value, which is `-log2(t)`.
+    pub fn write(self, meta: &Metadata) -> Result<()> {
+        meta.set_tag_rational("Exif.Photo.ExposureTime", &self.0)?;
+        // The APEX value is negative for exposures longer than a second, which only
+        // survives when Exiv2 parses it as the signed rational that the tag is defined as.
+        let apex = hundredths(-to_f64(self.0).log2());
+        meta.set_tag_string(
+            "Exif.Photo.ShutterSpeedValue",
+            &format!("{}/{}", apex.numer(), apex.denom()),
+        )?;
+        Ok(())
+    }
+}
+
+impl FromStr for ExposureTime {
+    type Err = Error;
+
+    /// Parses exposure times written as fractions like `1/125`, decimals like `0.5`
+    /// or whole seconds like `2s` or `2"`.
+    fn from_str(s: &str) -> Result<Self> {
+        let seconds = s.trim().trim_end_matches(['s', '"']).trim_end();
+        parse_rational(seconds)
+            .filter(|t| *t > Ratio::from_integer(0))
+            .map(ExposureTime)
+            .ok_or_else(|| anyhow!("Invalid shutter speed: {s}"))
+    }
+}
+
+deserialize_from_str!(Aperture, ExposureTime);
+
+fn to_f64(value: Ratio<i32>) -> f64 {
+    f64::from(*value.numer()) / f64::from(*value.denom())
+}
+
+// APEX values are small, so two decimals are plenty and always fit.
+#[allow(clippy::cast_possible_truncation)]
+fn hundredths(value: f64) -> Ratio<i32> {
+    Ratio::new((value * 100.0).round() as i32, 100)
+}
+
+/// Parses a fraction like `1/125` or a decimal number like `5.6` into an exact rational.
+/// Negative numbers are rejected, as none of the values that are parsed can be negative.
+pub fn parse_rational(s: &str) -> Option<Ratio<i32>> {
+    if s.contains('-') {
+        return None;
+    }
+
+    if let Some((numer, denom)) = s.split_once('/') {
+        let numer = numer.trim().parse().ok()?;
+        let denom = denom.trim().parse().ok()?;
+        return (denom != 0).then(|| Ratio::new(numer, denom));
+    }
+
+    let (whole, fraction) = s.split_once('.').unwrap_or((s, ""));
+    if (whole.is_empty() && fraction.is_empty()) || !fraction.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    let digits = u32::try_from(fraction.len()).ok()?;
+    let denom = 10_i32.checked_pow(digits)?;
+    let whole: i32 = if whole.is_empty() {
+        0
+    } else {
+        whole.parse().ok()?
+    };
+    let fraction: i32 = if fraction.is_empty() {
+        0
+    } else {
+        fraction.parse().ok()?
+    };
+    let numer = whole.checked_mul(denom)?.checked_add(fraction)?;
+    Some(Ratio::new(numer, denom))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_fractions_and_decimals() {
+        assert_eq!(parse_rational("1/125"), Some(Ratio::new(1, 125)));
+        assert_eq!(parse_rational("10/4"), Some(Ratio::new(5, 2)));
+        assert_eq!(parse_rational("5.6"), Some(Ratio::new(56, 10)));
+        assert_eq!(parse_rational("0.5"), Some(Ratio::new(1, 2)));
+        assert_eq!(parse_rational(".5"), Some(Ratio::new(1, 2)));
+        assert_eq!(parse_rational("8."), Some(Ratio::from_integer(8)));
+        assert_eq!(parse_rational("11"), Some(Ratio::from_integer(11)));
+    }
+
+    #[test]
+    fn rejects_invalid_rationals() {
+        for value in [
+            "", ".", "-0.5", "-1/125", "1/-125", "1/0", "5.6.1", "5,6", "f8", "1e3",
+        ] {
+            assert_eq!(parse_rational(value), None, "{value}");
+        }
+    }
+
+    #[test]
+    fn parses_apertures() {
+        for value in ["f/8", "F8", "f8", "8", " f/8 "] {
+            assert_eq!(value.parse::<Aperture>().unwrap().0, Ratio::from_integer(8));
+        }
+        assert_eq!("f/0.95".parse::<Aperture>().unwrap().0, Ratio::new(95, 100));
+        for value in ["f/0", "f/-2", "f/", "wide open"] {
+            assert!(value.parse::<Aperture>().is_err(), "{value}");
+        }
+    }
+
+    #[test]
+    fn parses_shutter_speeds() {
+        let shutter = |value: &str| value.parse::<ExposureTime>().unwrap().0;
+        assert_eq!(shutter("1/125"), Ratio::new(1, 125));
+        assert_eq!(shutter("0.5"), Ratio::new(1, 2));
+        assert_eq!(shutter("2s"), Ratio::from_integer(2));
+        assert_eq!(shutter("2\""), Ratio::from_integer(2));
+        assert_eq!(shutter("30 s"), Ratio::from_integer(30));
+        for value in ["0", "-0.5", "1/0", "bulb", ""] {
+            assert!(value.parse::<ExposureTime>().is_err(), "{value}");
+        }
+    }
+}
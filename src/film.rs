@@ -0,0 +1,133 @@
+use crate::catalog::{self, Named};
+use anyhow::{Context, Result, anyhow};
+use rexiv2::Metadata;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+
+const BUNDLED: &str = include_str!("../data/films.toml");
+
+/// The XMP namespace of `AnalogExif`, which photo managers and other film tools read film data from.
+const ANALOG_EXIF_NAMESPACE: &str = "http://analogexif.sourceforge.net/ns";
+
+/// Registers the `AnalogExif` namespace with Exiv2, which has to be done before any of its tags are written.
+pub fn register_namespace() -> Result<()> {
+    rexiv2::register_xmp_namespace(ANALOG_EXIF_NAMESPACE, "AnalogExif")?;
+    Ok(())
+}
+
+/// The chemical process used for developing a film.
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum Process {
+    #[serde(rename = "C-41")]
+    C41,
+    #[serde(rename = "E-6")]
+    E6,
+    #[serde(rename = "B&W")]
+    BlackAndWhite,
+    #[serde(rename = "ECN-2")]
+    Ecn2,
+    Instant,
+}
+
+impl fmt::Display for Process {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::C41 => "C-41",
+            Self::E6 => "E-6",
+            Self::BlackAndWhite => "B&W",
+            Self::Ecn2 => "ECN-2",
+            Self::Instant => "Instant",
+        })
+    }
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Film {
+    pub manufacturer: String,
+    pub name: String,
+    /// The box speed of the film.
+    pub iso: u16,
+    pub process: Process,
+    /// Set for monochrome films that are not developed as black and white, like Ilford XP2.
+    #[serde(default)]
+    monochrome: bool,
+    #[serde(default)]
+    aliases: Vec<String>,
+}
+
+impl Film {
+    pub fn full_name(&self) -> String {
+        format!("{} {}", self.manufacturer, self.name)
+    }
+
+    pub fn is_monochrome(&self) -> bool {
+        self.monochrome || self.process == Process::BlackAndWhite
+    }
+
+    /// Writes the film stock and its process to the `AnalogExif` XMP namespace.
+    pub fn write_xmp(&self, meta: &Metadata) -> Result<()> {
+        meta.set_tag_string("Xmp.AnalogExif.FilmMaker", &self.manufacturer)?;
+        meta.set_tag_string("Xmp.AnalogExif.Film", &self.name)?;
+        meta.set_tag_string("Xmp.AnalogExif.DevelopProcess", &self.process.to_string())?;
+        Ok(())
+    }
+
+    /// Keywords that make the film searchable in photo managers.
+    pub fn keywords(&self) -> Vec<String> {
+        let kind = if self.is_monochrome() {
+            "black and white"
+        } else {
+            "color"
+        };
+
+        vec![
+            "film".to_string(),
+            self.manufacturer.clone(),
+            self.full_name(),
+            self.process.to_string(),
+            kind.to_string(),
+        ]
+    }
+}
+
+impl Named for Film {
+    fn names(&self) -> (&str, &str, &[String]) {
+        (&self.manufacturer, &self.name, &self.aliases)
+    }
+}
+
+/// A database of film stocks, made up of the bundled one and the user's `films.toml`.
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Films {
+    films: BTreeMap<String, Film>,
+}
+
+impl Films {
+    pub fn load() -> Result<Self> {
+        let mut films: Self = toml::from_str(BUNDLED).context("Invalid bundled film database")?;
+
+        if let Some(path) = catalog::user_database("films.toml") {
+            let content = fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read film database {}", path.display()))?;
+            let user: Self = toml::from_str(&content)
+                .with_context(|| format!("Invalid film database {}", path.display()))?;
+            films.films.extend(user.films);
+        }
+
+        Ok(films)
+    }
+
+    /// Looks up a film stock by name, suggesting the closest match for unknown ones.
+    pub fn get(&self, name: &str) -> Result<&Film> {
+        catalog::find(&self.films, name).ok_or_else(|| match catalog::suggest(&self.films, name) {
+            Some(suggestion) => anyhow!("Unknown film stock {name}, did you mean {suggestion}?"),
+            None => anyhow!(
+                "Unknown film stock {name}, add it to films.toml in the config directory to use it"
+            ),
+        })
+    }
+}
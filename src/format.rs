@@ -0,0 +1,244 @@
+use crate::catalog::normalize;
+use anyhow::{Error, Result, anyhow};
+use num_rational::Ratio;
+use rexiv2::Metadata;
+use serde::{Deserialize, Deserializer};
+use std::fmt;
+use std::str::FromStr;
+
+const MILLIMETERS_PER_INCH: f64 = 25.4;
+
+/// The diagonal of a 35mm film frame, which crop factors are relative to.
+const FULL_FRAME_DIAGONAL: f64 = 43.267;
+
+/// The size of the frames on a roll or sheet of film.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Format {
+    #[default]
+    Film135,
+    HalfFrame,
+    XPan,
+    Medium645,
+    Medium6x6,
+    Medium6x7,
+    Medium6x9,
+    Sheet4x5,
+    Sheet8x10,
+    InstaxMini,
+    InstaxSquare,
+    InstaxWide,
+    Polaroid,
+}
+
+impl Format {
+    const ALL: [Self; 13] = [
+        Self::Film135,
+        Self::HalfFrame,
+        Self::XPan,
+        Self::Medium645,
+        Self::Medium6x6,
+        Self::Medium6x7,
+        Self::Medium6x9,
+        Self::Sheet4x5,
+        Self::Sheet8x10,
+        Self::InstaxMini,
+        Self::InstaxSquare,
+        Self::InstaxWide,
+        Self::Polaroid,
+    ];
+
+    /// The names that the format can be given by, with the canonical name first.
+    fn names(self) -> &'static [&'static str] {
+        match self {
+            Self::Film135 => &["135", "35mm", "full-frame"],
+            Self::HalfFrame => &["half-frame", "half"],
+            Self::XPan => &["XPan", "TX-1"],
+            Self::Medium645 => &["645", "6x4.5"],
+            Self::Medium6x6 => &["6x6"],
+            Self::Medium6x7 => &["6x7"],
+            Self::Medium6x9 => &["6x9"],
+            Self::Sheet4x5 => &["4x5"],
+            Self::Sheet8x10 => &["8x10"],
+            Self::InstaxMini => &["Instax Mini", "instax"],
+            Self::InstaxSquare => &["Instax Square"],
+            Self::InstaxWide => &["Instax Wide"],
+            Self::Polaroid => &["Polaroid", "i-Type", "600", "SX-70"],
+        }
+    }
+
+    /// The width and height of the image area in millimeters, with the long side first.
+    fn size(self) -> (f64, f64) {
+        match self {
+            Self::Film135 => (36.0, 24.0),
+            Self::HalfFrame => (24.0, 18.0),
+            Self::XPan => (65.0, 24.0),
+            Self::Medium645 => (56.0, 41.5),
+            Self::Medium6x6 => (56.0, 56.0),
+            Self::Medium6x7 => (70.0, 56.0),
+            Self::Medium6x9 => (84.0, 56.0),
+            Self::Sheet4x5 => (120.0, 95.0),
+            Self::Sheet8x10 => (250.0, 200.0),
+            Self::InstaxMini => (62.0, 46.0),
+            Self::InstaxSquare => (62.0, 62.0),
+            Self::InstaxWide => (99.0, 62.0),
+            Self::Polaroid => (79.0, 77.0),
+        }
+    }
+
+    /// The number of frames on a 36 exposure 135 roll, a 120 roll or in a pack of instant film.
+    /// Sheet film has one frame per sheet and no fixed count.
+    fn frames(self) -> Option<usize> {
+        match self {
+            Self::Film135 => Some(36),
+            Self::HalfFrame => Some(72),
+            Self::XPan => Some(21),
+            Self::Medium645 => Some(15),
+            Self::Medium6x6 => Some(12),
+            Self::Medium6x7 | Self::InstaxMini | Self::InstaxSquare | Self::InstaxWide => Some(10),
+            Self::Medium6x9 | Self::Polaroid => Some(8),
+            Self::Sheet4x5 | Self::Sheet8x10 => None,
+        }
+    }
+
+    /// The most frames that a roll can plausibly hold. Careful loading gets a frame or two more
+    /// out of 135 film and one more out of 120 film, while packs of instant film are exact.
+    pub fn max_frames(self) -> Option<usize> {
+        let spare = match self {
+            Self::Film135 | Self::XPan => 2,
+            Self::HalfFrame => 4,
+            Self::Medium645 | Self::Medium6x6 | Self::Medium6x7 | Self::Medium6x9 => 1,
+            _ => 0,
+        };
+        self.frames().map(|frames| frames + spare)
+    }
+
+    /// How much narrower the field of view is than for the same focal length on 35mm film.
+    /// Formats larger than 35mm have a crop factor below one.
+    pub fn crop_factor(self) -> f64 {
+        let (width, height) = self.size();
+        FULL_FRAME_DIAGONAL / width.hypot(height)
+    }
+
+    /// Records the format in XMP, together with the resolution that the frame was scanned at,
+    /// as calculated from the size of the scan in pixels and the size of the frame on film.
+    /// The size of the scan is read from `source`, which differs from `meta` when writing to a sidecar.
+    ///
+    /// The resolution is only an estimate, so it is not written over one that the scanner wrote.
+    pub fn write(self, meta: &Metadata, source: &Metadata) -> Result<()> {
+        meta.set_tag_string("Xmp.AnalogExif.FilmFormat", &self.to_string())?;
+
+        let scanned = [meta, source]
+            .into_iter()
+            .any(|meta| !is_placeholder(meta.get_tag_rational("Exif.Image.XResolution")));
+        if scanned {
+            return Ok(());
+        }
+
+        if let Some(resolution) =
+            self.resolution(source.get_pixel_width(), source.get_pixel_height())
+        {
+            let resolution = Ratio::from_integer(resolution);
+            meta.set_tag_rational("Exif.Image.XResolution", &resolution)?;
+            meta.set_tag_rational("Exif.Image.YResolution", &resolution)?;
+            meta.set_tag_numeric("Exif.Image.ResolutionUnit", 2)?;
+        }
+        Ok(())
+    }
+
+    /// The resolution in pixels per inch of a scan of the given size, matching the long side of the
+    /// scan with the long side of the frame. Scans usually include some of the border around the
+    /// frame, so this is slightly higher than what the scanner was set to.
+    // Scans are at most a few hundred thousand pixels wide, which always fits.
+    #[allow(clippy::cast_possible_truncation)]
+    fn resolution(self, width: i32, height: i32) -> Option<i32> {
+        if width <= 0 || height <= 0 {
+            return None;
+        }
+
+        let (long, _) = self.size();
+        let pixels = f64::from(width.max(height));
+        Some((pixels / (long / MILLIMETERS_PER_INCH)).round() as i32)
+    }
+}
+
+/// Whether a resolution is missing or only the 72 pixels per inch that many programs write
+/// when the real resolution is not known.
+fn is_placeholder(resolution: Option<Ratio<i32>>) -> bool {
+    resolution.is_none_or(|resolution| {
+        resolution <= Ratio::from_integer(0) || resolution == Ratio::from_integer(72)
+    })
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.names()[0])
+    }
+}
+
+impl FromStr for Format {
+    type Err = Error;
+
+    /// Parses formats by their names, like `135`, `6x7` or `instax wide`, ignoring case and separators.
+    fn from_str(s: &str) -> Result<Self> {
+        let wanted = normalize(s);
+        Self::ALL
+            .into_iter()
+            .find(|format| format.names().iter().any(|name| normalize(name) == wanted))
+            .ok_or_else(|| {
+                let known: Vec<&str> = Self::ALL.iter().map(|format| format.names()[0]).collect();
+                anyhow!(
+                    "Unknown film format: {s}, expected one of {}",
+                    known.join(", ")
+                )
+            })
+    }
+}
+
+impl<'de> Deserialize<'de> for Format {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        // Formats like 135 may also be written as TOML numbers.
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Raw {
+            Text(String),
+            Number(i64),
+        }
+
+        let text = match Raw::deserialize(deserializer)? {
+            Raw::Text(text) => text,
+            Raw::Number(number) => number.to_string(),
+        };
+        text.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn calculates_the_scan_resolution() {
+        assert_eq!(Format::Film135.resolution(5400, 3600), Some(3810));
+        assert_eq!(Format::Film135.resolution(3600, 5400), Some(3810));
+        assert_eq!(Format::Medium6x6.resolution(4000, 4000), Some(1814));
+        assert_eq!(Format::Film135.resolution(0, 0), None);
+    }
+
+    #[test]
+    fn keeps_resolutions_from_the_scanner() {
+        assert!(is_placeholder(None));
+        assert!(is_placeholder(Some(Ratio::from_integer(72))));
+        assert!(is_placeholder(Some(Ratio::new(144, 2))));
+        assert!(is_placeholder(Some(Ratio::from_integer(0))));
+        assert!(!is_placeholder(Some(Ratio::from_integer(3600))));
+        assert!(!is_placeholder(Some(Ratio::from_integer(300))));
+    }
+
+    #[test]
+    fn allows_spare_frames() {
+        assert_eq!(Format::Film135.max_frames(), Some(38));
+        assert_eq!(Format::Medium6x7.max_frames(), Some(11));
+        assert_eq!(Format::InstaxMini.max_frames(), Some(10));
+        assert_eq!(Format::Sheet4x5.max_frames(), None);
+    }
+}
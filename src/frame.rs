@@ -0,0 +1,141 @@
+use crate::de::deserialize_from_str;
+use anyhow::{Context, Error, Result, anyhow};
+use regex::Regex;
+use rexiv2::Metadata;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::LazyLock;
+
+/// Patterns for the file names written by common scanning setups, by preset name.
+const PRESETS: &[(&str, &str)] = &[
+    // `scan-0017.jpg`, with the frame as the digits at the end, as written by most scanning software.
+    ("trailing", r"(?<frame>\d+)$"),
+    // `R042_F17.tif` or `roll42-frame17.jpg`, with both numbers marked.
+    (
+        "roll-frame",
+        r"(?i)r(?:oll)?[_-]?(?<roll>\d+)[_ -]*f(?:rame)?[_-]?(?<frame>\d+)",
+    ),
+    // `042-17.jpg` or `042_17.jpg`, with the roll number before the frame number.
+    ("numbers", r"(?<roll>\d+)[_-](?<frame>\d+)\D*$"),
+    // `000017.jpg`, with nothing but the frame number, as exported by many lab scanners.
+    ("counter", r"^(?<frame>\d+)$"),
+];
+
+/// The roll and frame that a file is a scan of.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FrameNumber {
+    pub roll: Option<String>,
+    pub frame: u32,
+}
+
+impl FrameNumber {
+    /// Writes the frame number to Exif and both numbers to the `AnalogExif` XMP namespace.
+    pub fn write(&self, meta: &Metadata) -> Result<()> {
+        meta.set_tag_numeric("Exif.Image.ImageNumber", i32::try_from(self.frame)?)?;
+        meta.set_tag_string("Xmp.AnalogExif.FrameNumber", &self.frame.to_string())?;
+        if let Some(roll) = &self.roll {
+            meta.set_tag_string("Xmp.AnalogExif.RollId", roll)?;
+        }
+        Ok(())
+    }
+}
+
+/// A regular expression that extracts the frame number, and optionally the roll, from file names.
+#[derive(Clone, Debug)]
+pub struct FramePattern(Regex);
+
+impl FramePattern {
+    /// The pattern that is used when none is given, taking the digits at the end of the file name.
+    /// Names like `scan-0017a` have no frame number then, so that they are only matched by file name.
+    pub fn trailing() -> &'static Self {
+        static TRAILING: LazyLock<FramePattern> =
+            LazyLock::new(|| PRESETS[0].1.parse().expect("Presets are valid patterns"));
+        &TRAILING
+    }
+
+    /// Matches the pattern against the file name without its extension.
+    pub fn extract(&self, file: &Path) -> Option<FrameNumber> {
+        let stem = file.file_stem()?.to_str()?;
+        let captures = self.0.captures(stem)?;
+        let frame = captures.name("frame")?.as_str().parse().ok()?;
+        // Leading zeros are kept off so that `042` and `42` are the same roll.
+        let roll = captures.name("roll").map(|roll| {
+            let trimmed = roll.as_str().trim_start_matches('0');
+            if trimmed.is_empty() { "0" } else { trimmed }.to_string()
+        });
+        Some(FrameNumber { roll, frame })
+    }
+}
+
+impl FromStr for FramePattern {
+    type Err = Error;
+
+    /// Parses either the name of a preset or a regular expression with a `frame` group,
+    /// and optionally a `roll` group, like `R(?<roll>\d+)_F(?<frame>\d+)`.
+    fn from_str(s: &str) -> Result<Self> {
+        let pattern = PRESETS
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(s.trim()))
+            .map_or(s, |(_, pattern)| pattern);
+
+        let regex =
+            Regex::new(pattern).with_context(|| format!("Invalid frame pattern: {pattern}"))?;
+        if !regex.capture_names().any(|name| name == Some("frame")) {
+            let presets: Vec<&str> = PRESETS.iter().map(|(name, _)| *name).collect();
+            return Err(anyhow!(
+                "Frame pattern without a (?<frame>...) group: {s}, expected a regular expression or one of {}",
+                presets.join(", ")
+            ));
+        }
+        Ok(Self(regex))
+    }
+}
+
+deserialize_from_str!(FramePattern);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn extract(pattern: &str, file: &str) -> Option<FrameNumber> {
+        pattern
+            .parse::<FramePattern>()
+            .unwrap()
+            .extract(Path::new(file))
+    }
+
+    fn number(roll: Option<&str>, frame: u32) -> FrameNumber {
+        FrameNumber {
+            roll: roll.map(str::to_string),
+            frame,
+        }
+    }
+
+    #[test]
+    fn extracts_numbers_with_presets() {
+        assert_eq!(extract("trailing", "scan-0017.jpg"), Some(number(None, 17)));
+        assert_eq!(extract("trailing", "scan-0017a.jpg"), None);
+        assert_eq!(
+            extract("roll-frame", "R042_F17.tif"),
+            Some(number(Some("42"), 17))
+        );
+        assert_eq!(
+            extract("roll-frame", "roll42-frame17.jpg"),
+            Some(number(Some("42"), 17))
+        );
+        assert_eq!(
+            extract("numbers", "042-17.jpg"),
+            Some(number(Some("42"), 17))
+        );
+        assert_eq!(extract("counter", "000017.jpg"), Some(number(None, 17)));
+        assert_eq!(extract("counter", "a17.jpg"), None);
+    }
+
+    #[test]
+    fn extracts_numbers_with_regular_expressions() {
+        let pattern = r"R(?<roll>\d+)_F(?<frame>\d+)";
+        assert_eq!(extract(pattern, "R000_F3.jpg"), Some(number(Some("0"), 3)));
+        assert!(r"(\d+)".parse::<FramePattern>().is_err());
+        assert!(r"(?<frame>\d+".parse::<FramePattern>().is_err());
+    }
+}
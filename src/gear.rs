@@ -0,0 +1,212 @@
+use crate::catalog::{self, Named};
+use crate::exposure::parse_rational;
+use crate::format::Format;
+use anyhow::{Context, Error, Result, anyhow};
+use num_rational::Ratio;
+use rexiv2::Metadata;
+use serde::{Deserialize, Deserializer};
+use std::collections::BTreeMap;
+use std::fs;
+use std::str::FromStr;
+
+const BUNDLED: &str = include_str!("../data/gear.toml");
+
+#[derive(Deserialize, Clone, Default)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Camera {
+    pub make: String,
+    pub model: String,
+    #[serde(default)]
+    pub serial: Option<String>,
+    #[serde(default)]
+    aliases: Vec<String>,
+}
+
+#[derive(Deserialize, Clone, Default)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Lens {
+    pub make: String,
+    pub model: String,
+    #[serde(default)]
+    pub serial: Option<String>,
+    /// The focal length, or range of focal lengths for zoom lenses, like `50` or `24-70`.
+    #[serde(default)]
+    focal_length: Option<String>,
+    /// The widest aperture, or the widest aperture at each end of a zoom, like `1.8` or `3.5-4.5`.
+    #[serde(default)]
+    aperture: Option<String>,
+    #[serde(default)]
+    aliases: Vec<String>,
+}
+
+/// A database of cameras and lenses, made up of the bundled one and the user's `gear.toml`.
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Gear {
+    cameras: BTreeMap<String, Camera>,
+    lenses: BTreeMap<String, Lens>,
+}
+
+impl Gear {
+    pub fn load() -> Result<Self> {
+        let mut gear = Self::parse(BUNDLED).context("Invalid bundled gear database")?;
+
+        if let Some(path) = catalog::user_database("gear.toml") {
+            let content = fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read gear database {}", path.display()))?;
+            let user = Self::parse(&content)
+                .with_context(|| format!("Invalid gear database {}", path.display()))?;
+            gear.cameras.extend(user.cameras);
+            gear.lenses.extend(user.lenses);
+        }
+
+        Ok(gear)
+    }
+
+    fn parse(content: &str) -> Result<Self> {
+        let gear: Self = toml::from_str(content)?;
+        for (key, lens) in &gear.lenses {
+            if lens.focal_length.is_some() && lens.specification().is_none() {
+                return Err(anyhow!("Invalid focal length or aperture for lens {key}"));
+            }
+        }
+        Ok(gear)
+    }
+
+    /// Looks up a camera by name, falling back to using the first word as the maker.
+    pub fn camera(&self, name: &str) -> Camera {
+        catalog::find(&self.cameras, name)
+            .cloned()
+            .unwrap_or_else(|| {
+                let (make, model) = split_name(name);
+                Camera {
+                    make,
+                    model,
+                    ..Camera::default()
+                }
+            })
+    }
+
+    /// Looks up a lens by name, falling back to using the first word as the maker.
+    pub fn lens(&self, name: &str) -> Lens {
+        catalog::find(&self.lenses, name)
+            .cloned()
+            .unwrap_or_else(|| {
+                let (make, model) = split_name(name);
+                Lens {
+                    make,
+                    model,
+                    ..Lens::default()
+                }
+            })
+    }
+}
+
+impl Lens {
+    /// The value for `Exif.Photo.LensSpecification`: the shortest and longest focal lengths
+    /// followed by the widest aperture at each of them, where `0/0` marks an unknown aperture.
+    pub fn specification(&self) -> Option<String> {
+        let (min_focal, max_focal) = range(self.focal_length.as_deref()?)?;
+        let (min_aperture, max_aperture) = match self.aperture.as_deref() {
+            Some(aperture) => range(aperture)?,
+            None => (Ratio::new_raw(0, 0), Ratio::new_raw(0, 0)),
+        };
+
+        let values = [min_focal, max_focal, min_aperture, max_aperture];
+        let values: Vec<_> = values
+            .iter()
+            .map(|v| format!("{}/{}", v.numer(), v.denom()))
+            .collect();
+        Some(values.join(" "))
+    }
+
+    /// The focal length of a lens that does not zoom.
+    pub fn prime_focal_length(&self) -> Option<FocalLength> {
+        let (min, max) = range(self.focal_length.as_deref()?)?;
+        (min == max).then_some(FocalLength(min))
+    }
+}
+
+/// The focal length in millimeters that a frame was shot at.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FocalLength(pub Ratio<i32>);
+
+impl FocalLength {
+    /// Writes the focal length together with the focal length giving the same
+    /// field of view on 35mm film, as calculated from the film format.
+    pub fn write(self, meta: &Metadata, format: Format) -> Result<()> {
+        meta.set_tag_rational("Exif.Photo.FocalLength", &self.0)?;
+        // Earlier versions wrote the focal length to the Image IFD, where it would now be stale.
+        meta.clear_tag("Exif.Image.FocalLength");
+
+        let millimeters = f64::from(*self.0.numer()) / f64::from(*self.0.denom());
+        let equivalent = equivalent(millimeters * format.crop_factor());
+        meta.set_tag_numeric("Exif.Photo.FocalLengthIn35mmFilm", equivalent)?;
+        Ok(())
+    }
+}
+
+impl FromStr for FocalLength {
+    type Err = Error;
+
+    /// Parses focal lengths written like `50`, `37.5` or `80mm`.
+    fn from_str(s: &str) -> Result<Self> {
+        let millimeters = s.trim().trim_end_matches("mm").trim_end();
+        parse_rational(millimeters)
+            .filter(|value| *value > Ratio::from_integer(0))
+            .map(FocalLength)
+            .ok_or_else(|| anyhow!("Invalid focal length: {s}"))
+    }
+}
+
+impl<'de> Deserialize<'de> for FocalLength {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        // Focal lengths may be written both as strings and as TOML numbers.
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Raw {
+            Text(String),
+            Integer(i64),
+            Float(f64),
+        }
+
+        let text = match Raw::deserialize(deserializer)? {
+            Raw::Text(text) => text,
+            Raw::Integer(value) => value.to_string(),
+            Raw::Float(value) => value.to_string(),
+        };
+        text.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+impl Named for Camera {
+    fn names(&self) -> (&str, &str, &[String]) {
+        (&self.make, &self.model, &self.aliases)
+    }
+}
+
+impl Named for Lens {
+    fn names(&self) -> (&str, &str, &[String]) {
+        (&self.make, &self.model, &self.aliases)
+    }
+}
+
+// The 35mm equivalent is a whole number of millimeters, which fits easily for any real lens.
+#[allow(clippy::cast_possible_truncation)]
+fn equivalent(millimeters: f64) -> i32 {
+    millimeters.round() as i32
+}
+
+fn split_name(name: &str) -> (String, String) {
+    match name.split_once(' ') {
+        Some((make, model)) => (make.to_string(), model.to_string()),
+        None => (String::new(), name.to_string()),
+    }
+}
+
+fn range(s: &str) -> Option<(Ratio<i32>, Ratio<i32>)> {
+    match s.split_once('-') {
+        Some((min, max)) => Some((parse_rational(min.trim())?, parse_rational(max.trim())?)),
+        None => parse_rational(s.trim()).map(|value| (value, value)),
+    }
+}
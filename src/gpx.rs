@@ -0,0 +1,112 @@
+use crate::location::Coordinates;
+use anyhow::{Context, Result, anyhow};
+use std::fs;
+use std::path::Path;
+use time::{Duration, OffsetDateTime, format_description::well_known::Rfc3339};
+
+#[derive(Clone, Copy)]
+struct Point {
+    time: OffsetDateTime,
+    coordinates: Coordinates,
+}
+
+/// Timestamped positions recorded by a GPS logger while shooting.
+pub struct Track {
+    points: Vec<Point>,
+}
+
+impl Track {
+    /// Reads the track points from one or more GPX files into a single track.
+    pub fn from_paths(paths: &[impl AsRef<Path>]) -> Result<Self> {
+        let mut points = Vec::new();
+        for path in paths {
+            let path = path.as_ref();
+            read_points(path, &mut points)
+                .with_context(|| format!("Failed to read GPX file {}", path.display()))?;
+        }
+
+        points.sort_by_key(|point| point.time);
+        Ok(Self { points })
+    }
+
+    /// Finds the position at the given time, interpolating between the surrounding track points.
+    /// Times further than the maximum gap from the track are not matched.
+    pub fn locate(&self, time: OffsetDateTime, max_gap: Duration) -> Option<Coordinates> {
+        let next = self.points.partition_point(|point| point.time < time);
+        let before = next.checked_sub(1).map(|i| self.points[i]);
+        let after = self.points.get(next).copied();
+
+        match (before, after) {
+            (Some(before), Some(after)) if after.time - before.time <= max_gap => {
+                Some(interpolate(before, after, time))
+            }
+            _ => [before, after]
+                .into_iter()
+                .flatten()
+                .filter(|point| (point.time - time).abs() <= max_gap)
+                .min_by_key(|point| (point.time - time).abs())
+                .map(|point| point.coordinates),
+        }
+    }
+}
+
+fn interpolate(before: Point, after: Point, time: OffsetDateTime) -> Coordinates {
+    let span = (after.time - before.time).as_seconds_f64();
+    let t = if span > 0.0 {
+        (time - before.time).as_seconds_f64() / span
+    } else {
+        0.0
+    };
+
+    let (a, b) = (before.coordinates, after.coordinates);
+    let lerp = |a: f64, b: f64| a + (b - a) * t;
+    Coordinates {
+        latitude: lerp(a.latitude, b.latitude),
+        longitude: lerp(a.longitude, b.longitude),
+        altitude: a.altitude.zip(b.altitude).map(|(a, b)| lerp(a, b)),
+    }
+}
+
+fn read_points(path: &Path, points: &mut Vec<Point>) -> Result<()> {
+    let content = fs::read_to_string(path)?;
+    let document = roxmltree::Document::parse(&content)?;
+
+    let track_points = document
+        .descendants()
+        .filter(|node| matches!(node.tag_name().name(), "trkpt" | "rtept"));
+    for node in track_points {
+        let child_text = |name: &str| {
+            node.children()
+                .find(|child| child.tag_name().name() == name)
+                .and_then(|child| child.text())
+                .map(str::trim)
+        };
+
+        // Points without a timestamp can not be matched against frames.
+        let Some(time) = child_text("time") else {
+            continue;
+        };
+
+        let coordinate = |name: &str| -> Result<f64> {
+            let value = node
+                .attribute(name)
+                .ok_or_else(|| anyhow!("Track point without {name}"))?;
+            value
+                .trim()
+                .parse()
+                .with_context(|| format!("Invalid {name}: {value}"))
+        };
+
+        points.push(Point {
+            time: OffsetDateTime::parse(time, &Rfc3339)
+                .with_context(|| format!("Invalid time: {time}"))?,
+            coordinates: Coordinates {
+                latitude: coordinate("lat")?,
+                longitude: coordinate("lon")?,
+                altitude: child_text("ele").and_then(|ele| ele.parse().ok()),
+            },
+        });
+    }
+
+    Ok(())
+}
@@ -0,0 +1,186 @@
+use crate::diff::{self, Change};
+use crate::paths::file_name;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// The name of the file in each directory that records the changes made to the images in it.
+pub const JOURNAL_NAME: &str = ".rolltag-journal.toml";
+
+/// The changes made to one file in one run, with the earlier values needed to undo them.
+/// Entries are recorded under the name of the image, even when the changes were written to its sidecar.
+#[derive(Serialize, Deserialize)]
+pub struct Entry {
+    pub run: String,
+    pub file: String,
+    /// The name of the sidecar that the changes were written to instead of the image.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sidecar: Option<String>,
+    /// Whether the sidecar was created by the run, in which case undoing it removes the sidecar.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub created: bool,
+    #[serde(default, rename = "change")]
+    pub changes: Vec<Change>,
+}
+
+impl Entry {
+    /// The name of the file that the changes were written to.
+    pub fn target(&self) -> &str {
+        self.sidecar.as_deref().unwrap_or(&self.file)
+    }
+}
+
+/// The number of runs that are kept in each journal, with older runs being forgotten.
+const MAX_RUNS: usize = 20;
+
+/// All entries recorded for the images in a directory, oldest first.
+#[derive(Serialize, Deserialize, Default)]
+pub struct Journal {
+    #[serde(default, rename = "entry")]
+    entries: Vec<Entry>,
+}
+
+// Files are tagged in parallel, so appends are serialized to keep entries from interleaving.
+static APPENDING: Mutex<()> = Mutex::new(());
+
+impl Journal {
+    /// Loads the journal for the directory, which is empty if nothing has been recorded yet.
+    pub fn load(dir: &Path) -> Result<Self> {
+        let path = dir.join(JOURNAL_NAME);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read journal {}", path.display()))?;
+        toml::from_str(&content)
+            .with_context(|| format!("Failed to parse journal {}", path.display()))
+    }
+
+    /// Writes the journal back to the directory, removing the file when no entries are left.
+    pub fn save(&self, dir: &Path) -> Result<()> {
+        let path = dir.join(JOURNAL_NAME);
+        if self.entries.is_empty() {
+            if path.exists() {
+                fs::remove_file(&path)?;
+            }
+            return Ok(());
+        }
+
+        fs::write(&path, toml::to_string(self)?)
+            .with_context(|| format!("Failed to write journal {}", path.display()))
+    }
+
+    /// Puts entries that could not be undone back in the journal, in the order of their runs.
+    pub fn restore(&mut self, entries: Vec<Entry>) {
+        self.entries.extend(entries);
+        // Runs are RFC 3339 times in UTC, which sort by time as strings.
+        self.entries.sort_by(|a, b| a.run.cmp(&b.run));
+    }
+
+    /// Forgets all but the latest runs, to keep the journal from growing without bound.
+    pub fn prune(&mut self) {
+        let mut runs: Vec<&str> = self
+            .entries
+            .iter()
+            .map(|entry| entry.run.as_str())
+            .collect();
+        runs.sort_unstable();
+        runs.dedup();
+        if runs.len() <= MAX_RUNS {
+            return;
+        }
+
+        let oldest = runs[runs.len() - MAX_RUNS].to_string();
+        self.entries.retain(|entry| entry.run >= oldest);
+    }
+
+    /// Removes and returns the latest entry for the file, or its entry from the given run.
+    /// Sidecars can be given either by their own name or by the name of their image.
+    pub fn take_file(&mut self, file: &str, run: Option<&str>) -> Option<Entry> {
+        let index = self.entries.iter().rposition(|entry| {
+            (entry.file == file || entry.sidecar.as_deref() == Some(file))
+                && run.is_none_or(|run| entry.run == run)
+        })?;
+        Some(self.entries.remove(index))
+    }
+
+    /// Removes and returns all entries from the given run, or from the latest run if none is given.
+    /// The newest entries come first so that they can be undone in order.
+    pub fn take_run(&mut self, run: Option<&str>) -> Vec<Entry> {
+        let Some(run) = run
+            .map(str::to_string)
+            .or_else(|| self.entries.last().map(|entry| entry.run.clone()))
+        else {
+            return Vec::new();
+        };
+
+        let (mut taken, kept) = self
+            .entries
+            .drain(..)
+            .partition::<Vec<_>, _>(|entry| entry.run == run);
+        self.entries = kept;
+        taken.reverse();
+        taken
+    }
+}
+
+/// Appends the changes made to a file to the journal in its directory.
+/// Files without any changes are not recorded, and neither are tags that can not be reverted.
+///
+/// Changes written to a sidecar are recorded under the image, together with whether the sidecar was created.
+pub fn record(
+    file: &Path,
+    sidecar: Option<(&Path, bool)>,
+    run: &str,
+    changes: Vec<Change>,
+) -> Result<()> {
+    let (changes, lost): (Vec<Change>, Vec<Change>) = changes
+        .into_iter()
+        .partition(|change| diff::revertible(&change.tag));
+    if let Some(first) = lost.first() {
+        eprintln!(
+            "Undo can not restore {} binary or maker note tags of {}, like {}",
+            lost.len(),
+            file.display(),
+            first.tag
+        );
+    }
+    let created = sidecar.is_some_and(|(_, created)| created);
+    if changes.is_empty() && !created {
+        return Ok(());
+    }
+
+    let (dir, name) = split(file);
+    let journal = Journal {
+        entries: vec![Entry {
+            run: run.to_string(),
+            file: name,
+            sidecar: sidecar.map(|(sidecar, _)| file_name(sidecar)),
+            created,
+            changes,
+        }],
+    };
+    let content = toml::to_string(&journal)?;
+
+    let path = dir.join(JOURNAL_NAME);
+    let _guard = APPENDING
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    let mut out = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .with_context(|| format!("Failed to open journal {}", path.display()))?;
+    writeln!(out, "{content}")?;
+    Ok(())
+}
+
+/// Splits a path into the directory holding its journal and the name it is recorded under.
+pub fn split(file: &Path) -> (PathBuf, String) {
+    let dir = file.parent().unwrap_or(Path::new("")).to_path_buf();
+    (dir, file_name(file))
+}
@@ -0,0 +1,187 @@
+use crate::de::deserialize_from_str;
+use anyhow::{Error, Result, anyhow};
+use rexiv2::Metadata;
+use std::fmt;
+use std::str::FromStr;
+
+/// A position on Earth in WGS 84 coordinates, with the altitude in meters above sea level.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Coordinates {
+    pub latitude: f64,
+    pub longitude: f64,
+    pub altitude: Option<f64>,
+}
+
+/// The range of altitudes in meters that photos can plausibly be taken at, from below the Dead Sea to above airliners.
+const ALTITUDES: std::ops::RangeInclusive<f64> = -1_000.0..=20_000.0;
+
+impl Coordinates {
+    /// Whether the text is written as numbers separated by commas, like coordinates, rather than as the name of a place.
+    pub fn is_numeric(s: &str) -> bool {
+        let values: Vec<&str> = s.split(',').map(str::trim).collect();
+        (2..=3).contains(&values.len()) && values.iter().all(|value| value.parse::<f64>().is_ok())
+    }
+
+    /// Writes the position as Exif GPS tags, with the degrees, minutes and seconds as rationals
+    /// and the hemispheres in the reference tags.
+    pub fn write(&self, meta: &Metadata) -> Result<()> {
+        meta.set_tag_string("Exif.GPSInfo.GPSVersionID", "2 3 0 0")?;
+        meta.set_tag_string("Exif.GPSInfo.GPSMapDatum", "WGS-84")?;
+
+        let latitude_ref = if self.latitude < 0.0 { "S" } else { "N" };
+        meta.set_tag_string("Exif.GPSInfo.GPSLatitudeRef", latitude_ref)?;
+        meta.set_tag_string("Exif.GPSInfo.GPSLatitude", &degrees(self.latitude))?;
+
+        let longitude_ref = if self.longitude < 0.0 { "W" } else { "E" };
+        meta.set_tag_string("Exif.GPSInfo.GPSLongitudeRef", longitude_ref)?;
+        meta.set_tag_string("Exif.GPSInfo.GPSLongitude", &degrees(self.longitude))?;
+
+        // A stale altitude from an earlier position would be misleading.
+        if let Some(altitude) = self.altitude {
+            let altitude_ref = if altitude < 0.0 { "1" } else { "0" };
+            meta.set_tag_string("Exif.GPSInfo.GPSAltitudeRef", altitude_ref)?;
+            meta.set_tag_string("Exif.GPSInfo.GPSAltitude", &hundredths(altitude.abs()))?;
+        } else {
+            meta.clear_tag("Exif.GPSInfo.GPSAltitudeRef");
+            meta.clear_tag("Exif.GPSInfo.GPSAltitude");
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for Coordinates {
+    /// Formats the coordinates the same way as they are parsed, like `59.329300,18.068600`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:.6},{:.6}", self.latitude, self.longitude)?;
+        if let Some(altitude) = self.altitude {
+            write!(f, ",{altitude:.1}")?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Coordinates {
+    type Err = Error;
+
+    /// Parses coordinates written as `LAT,LON` or `LAT,LON,ALT` in decimal degrees,
+    /// with negative values for south and west.
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || anyhow!("Invalid coordinates, expected LAT,LON[,ALT]: {s}");
+        let values = s
+            .split(',')
+            .map(|value| value.trim().parse::<f64>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| invalid())?;
+
+        let (latitude, longitude, altitude) = match values[..] {
+            [latitude, longitude] => (latitude, longitude, None),
+            [latitude, longitude, altitude] => (latitude, longitude, Some(altitude)),
+            _ => return Err(invalid()),
+        };
+        // Ranges never contain NaN, so it is rejected as well.
+        if !(-90.0..=90.0).contains(&latitude) || !(-180.0..=180.0).contains(&longitude) {
+            return Err(anyhow!("Coordinates out of range: {s}"));
+        }
+        if altitude.is_some_and(|altitude| !ALTITUDES.contains(&altitude)) {
+            return Err(anyhow!(
+                "Altitude out of range, expected {} to {} meters: {s}",
+                ALTITUDES.start(),
+                ALTITUDES.end()
+            ));
+        }
+
+        Ok(Self {
+            latitude,
+            longitude,
+            altitude,
+        })
+    }
+}
+
+deserialize_from_str!(Coordinates);
+
+// Latitudes and longitudes are at most 180 degrees, so the parts always fit.
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn degrees(value: f64) -> String {
+    let total = (value.abs() * 3600.0 * 10_000.0).round() as u64;
+    let seconds = total % (60 * 10_000);
+    let minutes = total / (60 * 10_000) % 60;
+    let degrees = total / (3600 * 10_000);
+    format!("{degrees}/1 {minutes}/1 {seconds}/10000")
+}
+
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn hundredths(value: f64) -> String {
+    format!("{}/100", (value * 100.0).round() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_coordinates() {
+        let parse = |value: &str| value.parse::<Coordinates>().unwrap();
+        assert_eq!(
+            parse("59.3293, 18.0686"),
+            Coordinates {
+                latitude: 59.3293,
+                longitude: 18.0686,
+                altitude: None,
+            }
+        );
+        assert_eq!(
+            parse("-33.8568,-151.2153,-12.5"),
+            Coordinates {
+                latitude: -33.8568,
+                longitude: -151.2153,
+                altitude: Some(-12.5),
+            }
+        );
+        assert!("90,180,20000".parse::<Coordinates>().is_ok());
+        assert!("-90,-180,-1000".parse::<Coordinates>().is_ok());
+    }
+
+    #[test]
+    fn rejects_invalid_coordinates() {
+        for value in [
+            "",
+            "59.3293",
+            "59.3293,18.0686,10,20",
+            "Stockholm",
+            "90.1,0",
+            "0,-180.1",
+            "0,0,-1000.1",
+            "0,0,20000.1",
+            "NaN,0",
+            "0,NaN",
+            "0,0,NaN",
+            "inf,0",
+        ] {
+            assert!(value.parse::<Coordinates>().is_err(), "{value}");
+        }
+    }
+
+    #[test]
+    fn tells_coordinates_from_place_names() {
+        assert!(Coordinates::is_numeric("59.3293,18.0686"));
+        assert!(Coordinates::is_numeric(" -33.8 , 151.2 , 10 "));
+        // Numbers out of range are still coordinates, so that they are reported rather than taken as a place.
+        assert!(Coordinates::is_numeric("91,0"));
+        assert!(Coordinates::is_numeric("NaN,0"));
+        assert!(!Coordinates::is_numeric("Stockholm"));
+        assert!(!Coordinates::is_numeric("Gamla stan, Stockholm"));
+        assert!(!Coordinates::is_numeric("59.3293"));
+        assert!(!Coordinates::is_numeric("1,2,3,4"));
+    }
+
+    #[test]
+    fn writes_degrees_minutes_and_seconds() {
+        assert_eq!(degrees(59.3293), "59/1 19/1 454800/10000");
+        // The hemisphere is written to the reference tags, so the value itself is never negative.
+        assert_eq!(degrees(-18.0686), "18/1 4/1 69600/10000");
+        assert_eq!(degrees(0.0), "0/1 0/1 0/10000");
+        assert_eq!(degrees(-180.0), "180/1 0/1 0/10000");
+    }
+}
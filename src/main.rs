@@ -1,128 +1,887 @@
-use anyhow::{Result, anyhow};
-use clap::Parser;
+mod catalog;
+mod check;
+mod config;
+mod date;
+mod de;
+mod diff;
+mod export;
+mod exposure;
+mod film;
+mod format;
+mod frame;
+mod gear;
+mod gpx;
+mod journal;
+mod location;
+mod paths;
+mod profile;
+mod rights;
+mod roll;
+mod selection;
+mod sequence;
+mod shotlog;
+mod show;
+mod sidecar;
+mod summary;
+mod template;
+mod timestamp;
+mod zone;
+
+use anyhow::{Context, Result, anyhow};
+use check::Field;
+use clap::{Parser, Subcommand};
+use date::Interval;
+use diff::{Change, Snapshot};
+use film::{Film, Films};
+use frame::{FrameNumber, FramePattern};
+use gear::Gear;
+use gpx::Track;
+use journal::Journal;
+use profile::Tags;
 use rayon::ThreadPoolBuilder;
 use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
 use rexiv2::Metadata;
-use std::fs;
+use roll::{Counter, Roll};
+use selection::Selection;
+use sequence::Position;
+use shotlog::{Shot, ShotLog};
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
-use time::{OffsetDateTime, macros::format_description};
-
-const DATE_TIME_FORMAT: &[time::format_description::FormatItem<'_>] =
-    format_description!("[year]:[month]:[day] [hour]:[minute]:[second]");
+use std::{env, fs};
+use tempfile::NamedTempFile;
+use template::Values;
+use time::format_description::well_known::Rfc3339;
+use time::{Duration, OffsetDateTime, PrimitiveDateTime};
+use timestamp::Captured;
+use zone::Zone;
 
 #[derive(Parser)]
-#[command(author, version, about, long_about = None)]
+#[command(author, version, about, long_about = None, args_conflicts_with_subcommands = true)]
 /// A tool for tagging Exif metadata to scanned images from film rolls.
-struct Args {
-    /// Source files to apply metadata to.
-    src: Vec<PathBuf>,
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
 
-    /// Set the film stock used.
-    #[arg(short, long)]
-    film: Option<String>,
-
-    /// Set the ISO film speed used.
-    #[arg(short, long)]
-    iso: Option<u16>,
+    /// Tagging is the default when no subcommand is given.
+    #[command(flatten)]
+    tag: Args,
+}
 
-    /// Set the camera model used.
-    /// First word is parsed as the camera maker while the rest is set as the camera model.
-    #[arg(short, long)]
-    camera: Option<String>,
+/// Options for tagging files with the metadata of the roll.
+#[derive(clap::Args)]
+struct Args {
+    #[command(flatten)]
+    selection: Selection,
 
-    /// Set the lens model used.
-    /// First word is parsed as the lens maker while the rest is set as the lens model.
-    #[arg(short, long)]
-    lens: Option<String>,
+    #[command(flatten)]
+    tags: Tags,
 
     /// Clear all metadata from the image before applying new metadata.
-    #[arg(short, long)]
+    #[arg(short = 'C', long)]
     clear: bool,
 
-    /// Set the artist name.
+    /// Read roll metadata from a TOML profile instead of the `roll.toml` next to the images.
+    /// Flags given on the command line take precedence over values from the profile.
     #[arg(short, long)]
-    artist: Option<String>,
+    profile: Option<PathBuf>,
 
-    /// Set the focal length of the lens used.
-    #[arg(short, long)]
-    focal_length: Option<u16>,
+    /// Read per-frame metadata from a CSV shot log.
+    /// Rows are matched to files by the `file` column or else by the `frame` column,
+    /// which is compared against the frame number in each file name.
+    #[arg(long)]
+    shot_log: Option<PathBuf>,
+
+    /// Set the digitized date to the capture date instead of the scan date,
+    /// for tools that sort images by the digitized date.
+    #[arg(long)]
+    digitized_as_original: bool,
+
+    /// Set the location of each frame from GPX track files recorded while shooting.
+    /// Frames are matched to the track by their capture time.
+    #[arg(long)]
+    gpx: Vec<PathBuf>,
+
+    /// Shift the capture times by this much before matching them to the GPX track,
+    /// like `-30s` or `1h`, to make up for a difference between the clocks.
+    #[arg(long, allow_hyphen_values = true)]
+    gpx_offset: Option<Interval>,
+
+    /// The longest time between a frame and the GPX track for it to still be matched.
+    /// Defaults to five minutes.
+    #[arg(long)]
+    gpx_max_gap: Option<Interval>,
+
+    /// Show the tags that would be added, changed or removed in each file without writing anything.
+    #[arg(short = 'n', long)]
+    dry_run: bool,
+
+    /// Write the metadata to XMP sidecar files instead of modifying the images,
+    /// merging it into any existing sidecars. Sidecars are named like `photo.ARW.xmp`
+    /// by default, or like `photo.xmp` with `--sidecar=replace`.
+    #[arg(
+        long,
+        value_enum,
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "append"
+    )]
+    sidecar: Option<sidecar::Naming>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Tag the files with the metadata of the roll. This is the default when no subcommand is given.
+    Tag(Box<Args>),
+
+    /// Print the film metadata of the files as a table, highlighting values that differ across the roll.
+    Show(Selection),
+
+    /// Check that the files have all required metadata, listing the fields that are missing.
+    Check {
+        #[command(flatten)]
+        selection: Selection,
+
+        /// The fields that every file must have, separated by commas.
+        /// Defaults to the film, ISO, camera and capture date.
+        #[arg(long, value_enum, value_delimiter = ',')]
+        require: Vec<Field>,
+    },
+
+    /// Export the per-frame metadata of the files as a CSV shot log, which can be edited
+    /// and applied again with `--shot-log`.
+    Export {
+        #[command(flatten)]
+        selection: Selection,
+
+        /// Write the shot log to this file instead of to stdout.
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Restore the tags that were changed in the files by an earlier run, using the journal
+    /// kept in each directory. Files are restored to before their latest change, and
+    /// directories to before the latest run that changed any file in them.
+    Undo {
+        /// Files or directories to restore.
+        #[arg(required = true)]
+        paths: Vec<PathBuf>,
+
+        /// Undo the changes from this run, as recorded in the journal, instead of the latest one.
+        #[arg(long)]
+        run: Option<String>,
+    },
+}
+
+/// Everything that is known about a single image before it is tagged.
+struct Frame<'a> {
+    file: &'a PathBuf,
+    tags: Tags,
+    shot: Option<&'a Shot>,
+    /// The frame number from the file name, using the digits at its end if no pattern was given.
+    number: Option<FrameNumber>,
+    /// The roll that was given or numbered from the counter.
+    roll: Option<String>,
+    position: Position,
+    zone: Zone,
+}
+
+impl Frame<'_> {
+    /// The numbers from the file name, if they were extracted with a pattern that was asked for.
+    /// Numbers guessed from the file name without one are only used for matching the shot log.
+    fn extracted(&self) -> Option<&FrameNumber> {
+        self.number
+            .as_ref()
+            .filter(|_| self.tags.frame_pattern.is_some())
+    }
+
+    /// The roll that was given, or else the one from the file name.
+    fn roll(&self) -> Option<&str> {
+        self.roll
+            .as_deref()
+            .or_else(|| self.extracted().and_then(|number| number.roll.as_deref()))
+    }
 }
 
 fn main() -> Result<()> {
-    let args = Args::parse();
-    if args.src.is_empty() {
-        return Err(anyhow!("No files were provided"));
+    let cli = Cli::parse();
+    film::register_namespace()?;
+    timestamp::register_namespace()?;
+    match &cli.command {
+        Some(Command::Tag(args)) => tag(args),
+        Some(Command::Show(selection)) => show::show(&selection.files()?),
+        Some(Command::Check { selection, require }) => {
+            let required = if require.is_empty() {
+                &Field::DEFAULT[..]
+            } else {
+                require
+            };
+            check::check(&selection.files()?, required)
+        }
+        Some(Command::Export { selection, output }) => {
+            export::export(&selection.files()?, output.as_deref())
+        }
+        Some(Command::Undo { paths, run }) => undo(paths, run.as_deref()),
+        None => tag(&cli.tag),
     }
-    if args.iso.is_none() && args.camera.is_none() {
+}
+
+fn tag(args: &Args) -> Result<()> {
+    let scans = args.selection.files()?;
+    let tags = resolve_tags(args, &scans)?;
+    let modifies = args.shot_log.is_some() || !args.gpx.is_empty() || args.digitized_as_original;
+    if tags.iter().all(Tags::is_empty) && !modifies {
         return Err(anyhow!("No flags for modifying the metadata were provided"));
     }
 
-    ThreadPoolBuilder::new().build()?.install(|| -> Result<()> {
-        args.src
+    // Defaults are only merged in now, as they alone should not cause any file to be rewritten.
+    let defaults = config::user_defaults()?;
+    let tags: Vec<Tags> = tags
+        .into_iter()
+        .map(|tags| tags.or(defaults.clone()))
+        .collect();
+
+    let gear = Gear::load()?;
+    let films = Films::load()?;
+    for film in tags.iter().filter_map(|tags| tags.film.as_deref()) {
+        films.get(film)?;
+    }
+
+    let track = (!args.gpx.is_empty())
+        .then(|| Track::from_paths(&args.gpx))
+        .transpose()?;
+    let shot_log = args
+        .shot_log
+        .as_deref()
+        .map(ShotLog::from_path)
+        .transpose()?;
+
+    let (rolls, counter) = number_rolls(args, &tags, &scans)?;
+    let run = OffsetDateTime::now_utc().format(&Rfc3339)?;
+    let local = Zone::local();
+    let frames: Vec<Frame> = scans
+        .iter()
+        .zip(tags)
+        .zip(rolls)
+        .zip(sequence::positions(&scans))
+        .map(|(((file, tags), roll), position)| {
+            let pattern = tags.frame_pattern.as_ref();
+            let number = pattern.unwrap_or(FramePattern::trailing()).extract(file);
+            if pattern.is_some() && number.is_none() {
+                eprintln!("No frame number in the name of {}", file.display());
+            }
+
+            Frame {
+                file,
+                zone: tags.tz.unwrap_or(local),
+                shot: shot_log
+                    .as_ref()
+                    .and_then(|log| log.find(file, number.as_ref().map(|number| number.frame))),
+                tags,
+                number,
+                roll,
+                position,
+            }
+        })
+        .collect();
+
+    check_templates(&gear, &films, &frames)?;
+
+    // More frames than fit on a roll usually means that files from several rolls were mixed.
+    for frame in frames.iter().filter(|frame| frame.position.index == 0) {
+        let Some(format) = frame.tags.format else {
+            continue;
+        };
+        match format.max_frames() {
+            Some(count) if frame.position.count > count => eprintln!(
+                "{} has {} frames, more than the {count} that fit on a {format} roll",
+                frame.file.parent().unwrap_or(frame.file).display(),
+                frame.position.count,
+            ),
+            _ => {}
+        }
+    }
+
+    // Reports are collected before printing to keep them in the same order as the files.
+    let reports = ThreadPoolBuilder::new().build()?.install(|| {
+        frames
             .par_iter()
-            .try_for_each(|path| -> Result<()> { apply_metadata(&args, path) })
-    })
+            .map(|frame| apply_metadata(args, &run, &gear, &films, track.as_ref(), frame))
+            .collect::<Result<Vec<_>>>()
+    })?;
+    for report in reports.into_iter().flatten() {
+        print!("{report}");
+    }
+
+    if !args.dry_run {
+        if let Some(counter) = counter {
+            counter.save()?;
+        }
+
+        let mut dirs: Vec<PathBuf> = scans.iter().map(|file| journal::split(file).0).collect();
+        dirs.sort();
+        dirs.dedup();
+        for dir in dirs {
+            let mut journal = Journal::load(&dir)?;
+            journal.prune();
+            journal.save(&dir)?;
+        }
+    }
+
+    Ok(())
 }
 
-fn apply_metadata(args: &Args, file: &PathBuf) -> Result<()> {
-    let meta = Metadata::new_from_path(file)?;
+// Combines the flags with the roll profile for each file, either the one given
+// explicitly or the one found in the same directory as the file. Flags win over the roll profile.
+fn resolve_tags<'a>(args: &Args, files: &'a [PathBuf]) -> Result<Vec<Tags>> {
+    let profile = args.profile.as_deref().map(Tags::from_path).transpose()?;
+    let mut discovered: HashMap<&'a Path, Option<Tags>> = HashMap::new();
+
+    let mut resolved = Vec::with_capacity(files.len());
+    for file in files {
+        let fallback = if let Some(profile) = &profile {
+            Some(profile.clone())
+        } else {
+            let dir = file.parent().unwrap_or(file);
+            if !discovered.contains_key(dir) {
+                discovered.insert(dir, Tags::discover(file)?);
+            }
+            discovered[dir].clone()
+        };
+        resolved.push(args.tags.clone().or(fallback.unwrap_or_default()));
+    }
+
+    Ok(resolved)
+}
+
+/// Expands the templates for every frame before anything is written, so that a value that is
+/// missing for one frame can not leave the roll half tagged. Capture dates are only known
+/// once the files are read, but there always is one, so any date can stand in for them.
+fn check_templates(gear: &Gear, films: &Films, frames: &[Frame]) -> Result<()> {
+    let now = OffsetDateTime::now_utc();
+    for frame in frames {
+        let tags = &frame.tags;
+        let stock = tags
+            .film
+            .as_deref()
+            .map(|film| films.get(film))
+            .transpose()?;
+        let values = template_values(gear, frame, stock, now);
+        let templates = [tags.copyright.as_deref(), tags.description.as_deref()];
+        for template in templates.into_iter().flatten() {
+            template::expand(template, &values).with_context(|| {
+                format!(
+                    "Failed to fill in the template for {}",
+                    frame.file.display()
+                )
+            })?;
+        }
+    }
+    Ok(())
+}
+
+/// Finds the roll of each file. Directories tagged with `--roll next` keep the roll that their files
+/// already have, or else take the next number from the counter, which is returned to be saved once
+/// the files have been tagged.
+fn number_rolls(
+    args: &Args,
+    tags: &[Tags],
+    scans: &[PathBuf],
+) -> Result<(Vec<Option<String>>, Option<Counter>)> {
+    if tags.iter().all(|tags| tags.roll.is_none()) {
+        return Ok((vec![None; scans.len()], None));
+    }
+
+    let mut counter = Counter::load()?;
+    let mut numbered: HashMap<&Path, String> = HashMap::new();
+    let mut rolls = Vec::with_capacity(scans.len());
+    for (file, tags) in scans.iter().zip(tags) {
+        let roll = match &tags.roll {
+            Some(Roll::Id(id)) => {
+                counter.note(id);
+                Some(id.clone())
+            }
+            Some(Roll::Next) => {
+                let dir = file.parent().unwrap_or(file);
+                if !numbered.contains_key(dir) {
+                    let mut in_dir = scans.iter().filter(|scan| scan.parent() == file.parent());
+                    let id = in_dir
+                        .find_map(|scan| existing_roll(args, scan))
+                        .unwrap_or_else(|| {
+                            let id = counter.next();
+                            eprintln!("Numbered roll {id}, starting at {}", file.display());
+                            id
+                        });
+                    numbered.insert(dir, id);
+                }
+                Some(numbered[dir].clone())
+            }
+            None => None,
+        };
+        rolls.push(roll);
+    }
+
+    Ok((rolls, Some(counter)))
+}
+
+/// Reads the roll that a file was tagged with before, from its sidecar when writing sidecars.
+fn existing_roll(args: &Args, file: &Path) -> Option<String> {
+    let sidecar = args
+        .sidecar
+        .map(|naming| naming.path(file))
+        .filter(|sidecar| sidecar.exists());
+    let meta = Metadata::new_from_path(sidecar.as_deref().unwrap_or(file)).ok()?;
+    let roll = meta.get_tag_string("Xmp.AnalogExif.RollId").ok()?;
+    let roll = roll.trim();
+    (!roll.is_empty()).then(|| roll.to_string())
+}
+
+fn apply_metadata(
+    args: &Args,
+    run: &str,
+    gear: &Gear,
+    films: &Films,
+    track: Option<&Track>,
+    frame: &Frame,
+) -> Result<Option<String>> {
+    let Frame {
+        file, tags, shot, ..
+    } = frame;
+    // Sidecars are written instead of the image, but existing values are still read from the image itself.
+    let (target, meta, source) = match args.sidecar {
+        Some(naming) => {
+            let target = naming.path(file);
+            let meta = sidecar::open(&target)?;
+            (target, meta, Some(Metadata::new_from_path(file)?))
+        }
+        None => ((*file).clone(), Metadata::new_from_path(file)?, None),
+    };
+    let source = source.as_ref().unwrap_or(&meta);
+    let before = diff::snapshot(&meta);
 
     if args.clear {
-        meta.clear_exif();
+        if args.sidecar.is_some() {
+            meta.clear();
+        } else {
+            meta.clear_exif();
+        }
+    }
+
+    let logged = shot.and_then(|shot| shot.date);
+    let sequence = sequence_time(tags, frame.position);
+    let captured = timestamp::set_timestamps(
+        file,
+        source,
+        &meta,
+        logged.or(sequence),
+        frame.zone,
+        args.digitized_as_original,
+    )?;
+    // The fractional seconds only order the frames within a sequence, and do not belong to a logged date.
+    if logged.is_some() {
+        meta.clear_tag("Exif.Photo.SubSecTimeOriginal");
+    } else if sequence.is_some() {
+        meta.set_tag_string(
+            "Exif.Photo.SubSecTimeOriginal",
+            &frame.position.sub_seconds(),
+        )?;
     }
 
-    set_timestamps(file, &meta)?;
+    apply_location(args, track, frame, captured, &meta)?;
 
-    if let Some(film) = &args.film {
-        meta.set_tag_string("Exif.Image.ImageDescription", film)?;
+    let stock = tags
+        .film
+        .as_deref()
+        .map(|film| films.get(film))
+        .transpose()?;
+    if let Some(stock) = stock {
+        stock.write_xmp(&meta)?;
+        add_keywords(&meta, &stock.keywords())?;
     }
 
-    if let Some(iso) = args.iso {
-        meta.set_tag_numeric("Exif.Photo.ISOSpeedRatings", i32::from(iso))?;
+    if let Some(format) = tags.format {
+        format.write(&meta, source)?;
     }
 
-    if let Some(camera) = &args.camera {
-        let (make, model) = camera.split_once(' ').unwrap_or_default();
-        meta.set_tag_string("Exif.Image.Make", make)?;
-        meta.set_tag_string("Exif.Image.Model", model)?;
+    if let Some(developer) = &tags.developer {
+        meta.set_tag_string("Xmp.AnalogExif.Developer", developer)?;
     }
 
-    if let Some(focal_length) = args.focal_length {
-        meta.set_tag_numeric("Exif.Image.FocalLength", i32::from(focal_length))?;
+    if let Some(number) = frame.extracted() {
+        number.write(&meta)?;
+    }
+    if let Some(roll) = &frame.roll {
+        meta.set_tag_string("Xmp.AnalogExif.RollId", roll)?;
     }
 
-    if let Some(lens) = &args.lens {
-        let (make, model) = lens.split_once(' ').unwrap_or_default();
-        meta.set_tag_string("Exif.Photo.LensMake", make)?;
-        meta.set_tag_string("Exif.Photo.LensModel", model)?;
+    if let Some(iso) = tags.iso.or(stock.map(|stock| stock.iso)) {
+        meta.set_tag_numeric("Exif.Photo.ISOSpeedRatings", i32::from(iso))?;
     }
 
-    if let Some(artist) = &args.artist {
+    apply_gear(gear, tags, &meta)?;
+
+    if let Some(artist) = &tags.artist {
         meta.set_tag_string("Exif.Image.Artist", artist)?;
     }
 
-    safe_write_metadata(file, &meta)
+    let values = template_values(gear, frame, stock, captured.time);
+    apply_templates(tags, &values, &meta, source)?;
+
+    // Exposures from the shot log are written later to take precedence over these.
+    if let Some(aperture) = tags.aperture {
+        aperture.write(&meta)?;
+    }
+
+    if let Some(shutter) = tags.shutter {
+        shutter.write(&meta)?;
+    }
+
+    if let Some(shot) = shot {
+        apply_shot(shot, &meta)?;
+    }
+
+    let (changes, staged) = compare(args, &target, &before, &meta)?;
+    if args.dry_run {
+        return Ok(Some(diff::render(&target, &changes)));
+    }
+
+    // The journal is written first so that a failed write can never leave changes that can not be undone.
+    let sidecar = args
+        .sidecar
+        .is_some()
+        .then(|| (target.as_path(), !target.exists()));
+    journal::record(file, sidecar, run, changes)?;
+    match staged {
+        Some(staged) => {
+            staged.persist(&target)?;
+        }
+        None => safe_write_metadata(&target, &meta)?,
+    }
+    Ok(None)
+}
+
+/// Finds the changes that tagging makes to the target. Exif tags only become the XMP tags that
+/// a sidecar holds once it is saved, so sidecars are compared after saving them to a temporary
+/// file, which is returned to replace the sidecar with, to record the tags that are actually written.
+fn compare(
+    args: &Args,
+    target: &Path,
+    before: &Snapshot,
+    meta: &Metadata,
+) -> Result<(Vec<Change>, Option<NamedTempFile>)> {
+    if args.sidecar.is_none() {
+        return Ok((diff::changes(before, &diff::snapshot(meta)), None));
+    }
+
+    let dir = if args.dry_run {
+        env::temp_dir()
+    } else {
+        target.parent().unwrap().to_path_buf()
+    };
+    let staged = stage(target, meta, &dir)?;
+    let saved = Metadata::new_from_path(staged.path())?;
+    Ok((diff::changes(before, &diff::snapshot(&saved)), Some(staged)))
+}
+
+/// Writes the location of the frame, from the shot log, the GPX track or the flags.
+fn apply_location(
+    args: &Args,
+    track: Option<&Track>,
+    frame: &Frame,
+    captured: Captured,
+    meta: &Metadata,
+) -> Result<()> {
+    let Frame {
+        file, tags, shot, ..
+    } = frame;
+    // Locations noted for the frame win over the track, which wins over the location of the roll.
+    // Frames without a known capture time can not be placed on the track, as the scan date stands in for it.
+    let tracked = track.filter(|_| !captured.estimated).and_then(|track| {
+        let offset = args.gpx_offset.map_or(Duration::ZERO, |offset| offset.0);
+        let max_gap = args.gpx_max_gap.map_or(Duration::minutes(5), |gap| gap.0);
+        track.locate(captured.time.checked_add(offset)?, max_gap)
+    });
+    let coordinates = shot.and_then(|shot| shot.coordinates);
+    if let Some(coordinates) = coordinates.or(tracked).or(tags.gps) {
+        coordinates.write(meta)?;
+    } else if track.is_some() && captured.estimated {
+        eprintln!(
+            "No capture date for matching {} to the GPX track",
+            file.display()
+        );
+    } else if track.is_some() {
+        eprintln!("No position in the GPX track for {}", file.display());
+    }
+    Ok(())
+}
+
+fn undo(paths: &[PathBuf], run: Option<&str>) -> Result<()> {
+    let mut failures = 0;
+    for path in paths {
+        let (dir, name) = if path.is_dir() {
+            (path.clone(), None)
+        } else {
+            let (dir, name) = journal::split(path);
+            (dir, Some(name))
+        };
+
+        let mut journal = Journal::load(&dir)?;
+        let entries = match &name {
+            Some(name) => journal.take_file(name, run).into_iter().collect(),
+            None => journal.take_run(run),
+        };
+        if entries.is_empty() {
+            eprintln!("Nothing to undo for {}", path.display());
+            continue;
+        }
+
+        // Files that fail to be restored keep their entries, so that undoing them can be tried again.
+        let mut failed = Vec::new();
+        for entry in entries {
+            let file = dir.join(entry.target());
+            if let Err(err) = revert(&file, &entry) {
+                eprintln!("Failed to undo the changes to {}: {err:#}", file.display());
+                failed.push(entry);
+            }
+        }
+        failures += failed.len();
+        journal.restore(failed);
+        journal.save(&dir)?;
+    }
+
+    if failures > 0 {
+        return Err(anyhow!("Failed to undo the changes to {failures} files"));
+    }
+    Ok(())
 }
 
-// This is required to ensure correct ordering when sorting files to avoid
-// using the modification date as the primary sorting key.
-fn set_timestamps(file: &Path, meta: &Metadata) -> Result<()> {
-    if let Ok(existing) = meta.get_tag_string("Exif.Photo.DateTimeOriginal") {
-        meta.set_tag_string("Exif.Photo.DateTimeDigitized", &existing)?;
+fn revert(file: &Path, entry: &journal::Entry) -> Result<()> {
+    // Sidecars created by the run did not exist before it, so nothing of them is kept.
+    if entry.created {
+        if file.exists() {
+            fs::remove_file(file)?;
+        }
         return Ok(());
     }
 
-    let mtime = file.metadata()?.modified()?;
-    let time = OffsetDateTime::from(mtime).format(DATE_TIME_FORMAT)?;
-    meta.set_tag_string("Exif.Photo.DateTimeOriginal", &time)?;
-    meta.set_tag_string("Exif.Photo.DateTimeDigitized", &time)?;
+    let meta = Metadata::new_from_path(file)?;
+    for change in &entry.changes {
+        change.revert(&meta)?;
+    }
+    safe_write_metadata(file, &meta)
+}
+
+// Frames are spread out over the shooting dates in filename order,
+// unless the shot log has the date for a frame.
+fn sequence_time(tags: &Tags, position: Position) -> Option<PrimitiveDateTime> {
+    if let Some(range) = tags.date_range {
+        return Some(range.spread(position.index, position.count));
+    }
+
+    let date = tags.date?;
+    let interval = tags.interval.unwrap_or_default();
+    let index = i32::try_from(position.index).unwrap_or(i32::MAX);
+    Some(date.0.saturating_add(interval.0.saturating_mul(index)))
+}
+
+/// Writes the description and copyright notice from their templates.
+/// The existing caption is read from `source` when `meta` has none, as sidecars start out empty.
+fn apply_templates(tags: &Tags, values: &Values, meta: &Metadata, source: &Metadata) -> Result<()> {
+    if let Some(description) = &tags.description {
+        let text = template::expand(description, values)?;
+        let existing = caption(meta).or_else(|| caption(source));
+        let mode = tags.description_mode.unwrap_or_default();
+        let description = mode.apply(existing.as_deref(), &text);
+        meta.set_tag_string("Exif.Image.ImageDescription", &description)?;
+        meta.set_tag_string("Xmp.dc.description", &description)?;
+    }
+
+    let notice = tags
+        .copyright
+        .as_deref()
+        .map(|copyright| template::expand(copyright, values))
+        .transpose()?;
+    rights::write(meta, notice.as_deref(), tags.license.as_ref())?;
+
     Ok(())
 }
 
-fn safe_write_metadata(file: &PathBuf, meta: &Metadata) -> Result<()> {
-    let temp = tempfile::NamedTempFile::new_in(file.parent().unwrap())?;
-    fs::copy(file, &temp)?;
-    meta.save_to_file(temp.path())?;
-    temp.persist(file)?;
+/// Reads the description of an image, from Exif or else from XMP, which is where sidecars keep it.
+fn caption(meta: &Metadata) -> Option<String> {
+    let text = |tag: &str| {
+        let value = meta.get_tag_string(tag).ok()?;
+        // Language alternatives in XMP are written like `lang="x-default" text`.
+        let value = match value.strip_prefix("lang=\"") {
+            Some(rest) => rest.split_once("\" ")?.1,
+            None => &value,
+        };
+        let value = value.trim();
+        (!value.is_empty()).then(|| value.to_string())
+    };
+    text("Exif.Image.ImageDescription").or_else(|| text("Xmp.dc.description"))
+}
+
+fn apply_gear(gear: &Gear, tags: &Tags, meta: &Metadata) -> Result<()> {
+    if let Some(camera) = &tags.camera {
+        let camera = gear.camera(camera);
+        if !camera.make.is_empty() {
+            meta.set_tag_string("Exif.Image.Make", &camera.make)?;
+        }
+        meta.set_tag_string("Exif.Image.Model", &camera.model)?;
+        if let Some(serial) = &camera.serial {
+            meta.set_tag_string("Exif.Photo.BodySerialNumber", serial)?;
+        }
+    }
+
+    if let Some(lens) = &tags.lens {
+        let lens = gear.lens(lens);
+        if !lens.make.is_empty() {
+            meta.set_tag_string("Exif.Photo.LensMake", &lens.make)?;
+        }
+        meta.set_tag_string("Exif.Photo.LensModel", &lens.model)?;
+        if let Some(serial) = &lens.serial {
+            meta.set_tag_string("Exif.Photo.LensSerialNumber", serial)?;
+        }
+        if let Some(specification) = lens.specification() {
+            meta.set_tag_string("Exif.Photo.LensSpecification", &specification)?;
+        }
+    }
+
+    // Prime lenses imply the focal length when it was not given explicitly.
+    let prime = tags
+        .lens
+        .as_deref()
+        .and_then(|lens| gear.lens(lens).prime_focal_length());
+    if let Some(focal_length) = tags.focal_length.or(prime) {
+        focal_length.write(meta, tags.format.unwrap_or_default())?;
+    }
+
+    Ok(())
+}
+
+// Values for the placeholders in templates, leaving out the ones that are not known for the frame.
+fn template_values(
+    gear: &Gear,
+    frame: &Frame,
+    stock: Option<&Film>,
+    captured: OffsetDateTime,
+) -> Values {
+    let Frame {
+        tags,
+        shot,
+        position,
+        ..
+    } = frame;
+
+    let mut values = Values::new();
+    values.insert("year", captured.year().to_string());
+    values.insert("date", captured.date().to_string());
+    if let Some(artist) = &tags.artist {
+        values.insert("artist", artist.clone());
+    }
+    if let Some(stock) = stock {
+        values.insert("film", stock.full_name());
+    }
+    if let Some(iso) = tags.iso.or(stock.map(|stock| stock.iso)) {
+        values.insert("iso", iso.to_string());
+    }
+    if let Some(developer) = &tags.developer {
+        values.insert("developer", developer.clone());
+    }
+    if let Some(camera) = &tags.camera {
+        let camera = gear.camera(camera);
+        values.insert(
+            "camera",
+            format!("{} {}", camera.make, camera.model)
+                .trim()
+                .to_string(),
+        );
+    }
+
+    let lens = tags.lens.as_deref().map(|lens| gear.lens(lens));
+    if let Some(lens) = &lens {
+        values.insert(
+            "lens",
+            format!("{} {}", lens.make, lens.model).trim().to_string(),
+        );
+    }
+    let prime = lens.and_then(|lens| lens.prime_focal_length());
+    if let Some(focal_length) = tags.focal_length.or(prime) {
+        values.insert("focal", format!("{}mm", summary::decimal(focal_length.0)));
+    }
+
+    let aperture = shot.and_then(|shot| shot.aperture).or(tags.aperture);
+    if let Some(aperture) = aperture {
+        values.insert("aperture", format!("f/{}", summary::decimal(aperture.0)));
+    }
+    let shutter = shot.and_then(|shot| shot.shutter).or(tags.shutter);
+    if let Some(shutter) = shutter {
+        values.insert("shutter", format!("{}s", shutter.0));
+    }
+
+    values.insert("format", tags.format.unwrap_or_default().to_string());
+    if let Some(roll) = frame.roll() {
+        values.insert("roll", roll.to_string());
+    }
+    let number = frame
+        .extracted()
+        .and_then(|number| usize::try_from(number.frame).ok())
+        .unwrap_or(position.index + 1);
+    values.insert("frame", number.to_string());
+    values.insert("frames", position.count.to_string());
+    values
+}
+
+// Keywords are merged with the existing ones to not lose any that were added by hand.
+fn add_keywords(meta: &Metadata, keywords: &[String]) -> Result<()> {
+    let mut merged = meta
+        .get_tag_multiple_strings("Xmp.dc.subject")
+        .unwrap_or_default();
+    for keyword in keywords {
+        if !merged.contains(keyword) {
+            merged.push(keyword.clone());
+        }
+    }
+
+    let merged: Vec<&str> = merged.iter().map(String::as_str).collect();
+    meta.set_tag_multiple_strings("Xmp.dc.subject", &merged)?;
+    Ok(())
+}
+
+// Per-frame values from the shot log are applied last to take precedence over the roll-wide flags.
+fn apply_shot(shot: &Shot, meta: &Metadata) -> Result<()> {
+    if let Some(aperture) = shot.aperture {
+        aperture.write(meta)?;
+    }
+
+    if let Some(shutter) = shot.shutter {
+        shutter.write(meta)?;
+    }
+
+    if let Some(notes) = &shot.notes {
+        meta.set_tag_string("Exif.Photo.UserComment", notes)?;
+    }
+
+    if let Some(location) = &shot.location {
+        meta.set_tag_string("Xmp.iptc.Location", location)?;
+    }
+
+    Ok(())
+}
+
+fn safe_write_metadata(file: &Path, meta: &Metadata) -> Result<()> {
+    stage(file, meta, file.parent().unwrap())?.persist(file)?;
     Ok(())
 }
+
+/// Saves the metadata to a temporary copy of the file in the directory, ready to replace the file.
+fn stage(file: &Path, meta: &Metadata, dir: &Path) -> Result<NamedTempFile> {
+    let temp = NamedTempFile::new_in(dir)?;
+    // Only sidecars are written without existing first.
+    if file.exists() {
+        fs::copy(file, &temp)?;
+    } else {
+        fs::write(&temp, sidecar::EMPTY_PACKET)?;
+    }
+    meta.save_to_file(temp.path())?;
+    Ok(temp)
+}
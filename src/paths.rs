@@ -0,0 +1,8 @@
+use std::path::Path;
+
+/// The name of the file without its directory, or an empty string for paths like `..`.
+pub fn file_name(file: &Path) -> String {
+    file.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
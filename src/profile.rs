@@ -0,0 +1,200 @@
+use crate::date::{DateRange, DateTime, Interval};
+use crate::exposure::{Aperture, ExposureTime};
+use crate::format::Format;
+use crate::frame::FramePattern;
+use crate::gear::FocalLength;
+use crate::location::Coordinates;
+use crate::rights::License;
+use crate::roll::Roll;
+use crate::template::Mode;
+use crate::zone::Zone;
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+
+/// The name of the roll profile that is picked up automatically from the directory of each image.
+pub const PROFILE_NAME: &str = "roll.toml";
+
+/// Metadata describing a roll, given either as flags or in a roll profile.
+#[derive(clap::Args, Deserialize, Default, Clone)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Tags {
+    /// Set the film stock used, by a name from the film database.
+    #[arg(short, long)]
+    pub film: Option<String>,
+
+    /// Set the film format, like `135`, `half-frame`, `xpan`, `645`, `6x6`, `6x7`, `6x9`, `4x5`,
+    /// `8x10`, `instax` or `polaroid`. Used for the 35mm equivalent focal length. Defaults to `135`.
+    #[arg(long)]
+    pub format: Option<Format>,
+
+    /// Set the ISO film speed used. Defaults to the box speed of the film stock.
+    #[arg(short, long)]
+    pub iso: Option<u16>,
+
+    /// Set the camera model used, either by a name from the gear database or by its full name.
+    /// For unknown cameras, the first word is parsed as the maker while the rest is set as the model.
+    #[arg(short, long)]
+    pub camera: Option<String>,
+
+    /// Set the lens model used, either by a name from the gear database or by its full name.
+    /// For unknown lenses, the first word is parsed as the maker while the rest is set as the model.
+    #[arg(short, long)]
+    pub lens: Option<String>,
+
+    /// Set the developer that the film was developed in, like `Rodinal 1+50`.
+    #[arg(long)]
+    pub developer: Option<String>,
+
+    /// Set the artist name.
+    #[arg(short, long)]
+    pub artist: Option<String>,
+
+    /// Set the copyright notice, which may use placeholders like `{year}` and `{artist}`,
+    /// as in `© {year} {artist}`. The year is taken from the capture date of each frame.
+    #[arg(long)]
+    pub copyright: Option<String>,
+
+    /// Set the description of the images from a template with placeholders like `{film}`,
+    /// `{iso}` or `{frame}`, as in `{film} @ ISO {iso}, frame {frame}`.
+    #[arg(long)]
+    pub description: Option<String>,
+
+    /// How the description is combined with any existing description,
+    /// which is kept as the caption unless it is replaced. Defaults to `append`.
+    #[arg(long, value_enum)]
+    pub description_mode: Option<Mode>,
+
+    /// Set the license that the images are published under, either a Creative Commons
+    /// license like `CC-BY-SA-4.0` or `CC0`, or the URL of any other license.
+    #[arg(long)]
+    pub license: Option<License>,
+
+    /// Set the focal length of the lens used in millimeters, like `50` or `37.5`.
+    /// Defaults to the focal length of prime lenses from the gear database.
+    #[arg(short = 'F', long)]
+    pub focal_length: Option<FocalLength>,
+
+    /// Set the aperture that the frames were exposed at, like `f/8` or `5.6`.
+    /// Frames in the shot log with their own aperture keep it.
+    #[arg(long)]
+    pub aperture: Option<Aperture>,
+
+    /// Set the shutter speed that the frames were exposed at, like `1/125` or `2s`.
+    /// Frames in the shot log with their own shutter speed keep it.
+    #[arg(long)]
+    pub shutter: Option<ExposureTime>,
+
+    /// Set the shooting date of the first frame, like `2024-05-12` or `2024-05-12 14:30`.
+    /// Later frames, in filename order, are each given a timestamp one interval later.
+    #[arg(short, long, conflicts_with = "date_range")]
+    pub date: Option<DateTime>,
+
+    /// Set the dates that the roll was shot between, like `2024-05-01..2024-05-20`.
+    /// Frames are spread out evenly over the range in filename order.
+    #[arg(long)]
+    pub date_range: Option<DateRange>,
+
+    /// Set the time between frames when spreading them out from the shooting date,
+    /// like `30s`, `5m` or `1h`. Defaults to one minute.
+    #[arg(long)]
+    pub interval: Option<Interval>,
+
+    /// Set the roll identifier, like `214`, which overrides the roll from the file names.
+    /// With `next`, each directory is numbered as the next roll from a counter kept in the config directory.
+    #[arg(long)]
+    pub roll: Option<Roll>,
+
+    /// Set the frame number, and the roll number if there is one, from the file names using
+    /// a preset like `trailing` for `scan-0017`, `roll-frame` for `R042_F17`, `numbers` for
+    /// `042-17` or `counter` for `000017`, or a regular expression with `frame` and `roll` groups.
+    #[arg(long)]
+    pub frame_pattern: Option<FramePattern>,
+
+    /// Set the time zone that the roll was shot in, either as a name like `Europe/Stockholm`
+    /// or as an offset like `+02:00`. Defaults to the time zone of the system.
+    #[arg(long)]
+    pub tz: Option<Zone>,
+
+    /// Set the location of the roll as `LAT,LON[,ALT]` in decimal degrees, like `59.33,18.07`,
+    /// for frames without a location from the shot log or a GPX track.
+    #[arg(long, allow_hyphen_values = true)]
+    pub gps: Option<Coordinates>,
+}
+
+impl Tags {
+    pub fn from_path(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read profile {}", path.display()))?;
+        toml::from_str(&content).with_context(|| format!("Invalid profile {}", path.display()))
+    }
+
+    /// Loads the roll profile next to the file, if there is one.
+    pub fn discover(file: &Path) -> Result<Option<Self>> {
+        let path = file.with_file_name(PROFILE_NAME);
+        if !path.is_file() {
+            return Ok(None);
+        }
+
+        Self::from_path(&path).map(Some)
+    }
+
+    /// Fills in the values that are missing with the ones from the fallback.
+    #[must_use]
+    pub fn or(self, fallback: Self) -> Self {
+        // A shooting date and a date range both describe when the roll was shot,
+        // so the fallback may only provide them if neither is set.
+        let (date, date_range) = if self.date.is_some() || self.date_range.is_some() {
+            (self.date, self.date_range)
+        } else {
+            (fallback.date, fallback.date_range)
+        };
+
+        Self {
+            film: self.film.or(fallback.film),
+            format: self.format.or(fallback.format),
+            iso: self.iso.or(fallback.iso),
+            camera: self.camera.or(fallback.camera),
+            lens: self.lens.or(fallback.lens),
+            developer: self.developer.or(fallback.developer),
+            artist: self.artist.or(fallback.artist),
+            copyright: self.copyright.or(fallback.copyright),
+            license: self.license.or(fallback.license),
+            description: self.description.or(fallback.description),
+            description_mode: self.description_mode.or(fallback.description_mode),
+            focal_length: self.focal_length.or(fallback.focal_length),
+            aperture: self.aperture.or(fallback.aperture),
+            shutter: self.shutter.or(fallback.shutter),
+            date,
+            date_range,
+            interval: self.interval.or(fallback.interval),
+            roll: self.roll.or(fallback.roll),
+            frame_pattern: self.frame_pattern.or(fallback.frame_pattern),
+            tz: self.tz.or(fallback.tz),
+            gps: self.gps.or(fallback.gps),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.film.is_none()
+            && self.format.is_none()
+            && self.iso.is_none()
+            && self.camera.is_none()
+            && self.lens.is_none()
+            && self.developer.is_none()
+            && self.artist.is_none()
+            && self.copyright.is_none()
+            && self.license.is_none()
+            && self.description.is_none()
+            && self.focal_length.is_none()
+            && self.aperture.is_none()
+            && self.shutter.is_none()
+            && self.date.is_none()
+            && self.date_range.is_none()
+            && self.tz.is_none()
+            && self.gps.is_none()
+            && self.roll.is_none()
+            && self.frame_pattern.is_none()
+    }
+}
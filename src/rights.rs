@@ -0,0 +1,169 @@
+use crate::de::deserialize_from_str;
+use anyhow::{Error, Result, anyhow};
+use rexiv2::Metadata;
+use std::str::FromStr;
+
+/// The license that images are published under, either a Creative Commons license or any other by its URL.
+#[derive(Clone, Debug, PartialEq)]
+pub struct License {
+    pub name: String,
+    pub url: String,
+    public_domain: bool,
+}
+
+impl FromStr for License {
+    type Err = Error;
+
+    /// Parses Creative Commons licenses written like `CC-BY-SA-4.0`, `by-nc` or `CC0`,
+    /// or any other license given by its URL.
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        if s.starts_with("https://") || s.starts_with("http://") {
+            return Ok(Self {
+                name: s.to_string(),
+                url: s.to_string(),
+                public_domain: false,
+            });
+        }
+
+        let lowercase = s.to_lowercase();
+        let mut elements: Vec<&str> = lowercase
+            .split(['-', ' ', '_'])
+            .filter(|element| !element.is_empty())
+            .collect();
+        if elements.first() == Some(&"cc") {
+            elements.remove(0);
+        }
+        // Only the current version of each license is known, so any other version is rejected
+        // rather than silently written as a license that the images were not published under.
+        let version = elements
+            .last()
+            .filter(|element| element.contains('.'))
+            .copied();
+        if version.is_some() {
+            elements.pop();
+        }
+
+        if matches!(elements[..], ["0" | "cc0" | "zero"]) {
+            if version.is_some_and(|version| version != "1.0") {
+                return Err(anyhow!("Unknown license: {s}, CC0 only has version 1.0"));
+            }
+            return Ok(Self {
+                name: "CC0 1.0".to_string(),
+                url: "https://creativecommons.org/publicdomain/zero/1.0/".to_string(),
+                public_domain: true,
+            });
+        }
+
+        let code = match elements[..] {
+            ["by"] => "by",
+            ["by", "sa"] => "by-sa",
+            ["by", "nd"] => "by-nd",
+            ["by", "nc"] => "by-nc",
+            ["by", "nc", "sa"] => "by-nc-sa",
+            ["by", "nc", "nd"] => "by-nc-nd",
+            _ => {
+                return Err(anyhow!(
+                    "Unknown license: {s}, expected a Creative Commons license like CC-BY-4.0 or a URL"
+                ));
+            }
+        };
+        if version.is_some_and(|version| version != "4.0") {
+            return Err(anyhow!(
+                "Unknown license: {s}, only version 4.0 of the Creative Commons licenses is supported"
+            ));
+        }
+        Ok(Self {
+            name: format!("CC {} 4.0", code.to_uppercase()),
+            url: format!("https://creativecommons.org/licenses/{code}/4.0/"),
+            public_domain: false,
+        })
+    }
+}
+
+deserialize_from_str!(License);
+
+/// Writes the copyright notice and license to both Exif and the XMP rights fields,
+/// which is where photo agencies and image search engines look for them.
+pub fn write(meta: &Metadata, notice: Option<&str>, license: Option<&License>) -> Result<()> {
+    if let Some(notice) = notice {
+        meta.set_tag_string("Exif.Image.Copyright", notice)?;
+        meta.set_tag_string("Xmp.dc.rights", notice)?;
+    }
+
+    if let Some(license) = license {
+        meta.set_tag_string("Xmp.xmpRights.WebStatement", &license.url)?;
+        meta.set_tag_string("Xmp.cc.license", &license.url)?;
+        meta.set_tag_string(
+            "Xmp.xmpRights.UsageTerms",
+            &format!(
+                "This work is licensed under {}: {}",
+                license.name, license.url
+            ),
+        )?;
+    }
+
+    // Works dedicated to the public domain are the only ones that are not rights managed.
+    if notice.is_some() || license.is_some() {
+        let marked = !license.is_some_and(|license| license.public_domain);
+        let marked = if marked { "True" } else { "False" };
+        meta.set_tag_string("Xmp.xmpRights.Marked", marked)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_creative_commons_licenses() {
+        for value in ["CC-BY-SA-4.0", "cc by-sa", "BY_SA", "CC BY-SA 4.0"] {
+            let license: License = value.parse().unwrap();
+            assert_eq!(license.name, "CC BY-SA 4.0", "{value}");
+            assert_eq!(
+                license.url,
+                "https://creativecommons.org/licenses/by-sa/4.0/"
+            );
+            assert!(!license.public_domain);
+        }
+        let license: License = "by-nc-nd".parse().unwrap();
+        assert_eq!(
+            license.url,
+            "https://creativecommons.org/licenses/by-nc-nd/4.0/"
+        );
+    }
+
+    #[test]
+    fn parses_public_domain_dedications() {
+        for value in ["CC0", "CC0-1.0", "cc-zero", "CC 0 1.0"] {
+            let license: License = value.parse().unwrap();
+            assert_eq!(license.name, "CC0 1.0", "{value}");
+            assert!(license.public_domain);
+        }
+    }
+
+    #[test]
+    fn parses_license_urls() {
+        let url = "https://example.com/license";
+        let license: License = url.parse().unwrap();
+        assert_eq!(license.url, url);
+        assert!(!license.public_domain);
+    }
+
+    #[test]
+    fn rejects_other_versions_and_unknown_licenses() {
+        for value in [
+            "CC-BY-1.0",
+            "CC-BY-NC-SA-1.0",
+            "CC-BY-3.0",
+            "CC0-4.0",
+            "CC-SA",
+            "GPL",
+            "",
+        ] {
+            assert!(value.parse::<License>().is_err(), "{value}");
+        }
+    }
+}
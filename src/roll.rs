@@ -0,0 +1,133 @@
+use crate::config::config_dir;
+use anyhow::{Context, Error, Result, anyhow};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::str::FromStr;
+
+/// The roll that the scans are from, either by its identifier or as the next roll from the counter.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Roll {
+    Id(String),
+    Next,
+}
+
+impl FromStr for Roll {
+    type Err = Error;
+
+    /// Parses `next` for the next number from the counter, or any other identifier like `214` or `2024-07`.
+    /// Leading zeros are left out of numbers, so that `0214` and `214` are the same roll.
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        if s.is_empty() {
+            return Err(anyhow!("Empty roll identifier"));
+        }
+        if s.eq_ignore_ascii_case("next") {
+            return Ok(Self::Next);
+        }
+        Ok(Self::Id(match s.parse::<u32>() {
+            Ok(number) => number.to_string(),
+            Err(_) => s.to_string(),
+        }))
+    }
+}
+
+impl<'de> Deserialize<'de> for Roll {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        // Roll numbers may also be written as TOML numbers.
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Raw {
+            Text(String),
+            Number(u32),
+        }
+
+        match Raw::deserialize(deserializer)? {
+            Raw::Text(text) => text.parse().map_err(serde::de::Error::custom),
+            Raw::Number(number) => Ok(Self::Id(number.to_string())),
+        }
+    }
+}
+
+/// The number of the last roll, kept in `rolls.toml` in the config directory so that rolls are numbered
+/// across runs. It is kept apart from `config.toml`, which is written by hand and never by rolltag.
+///
+/// The file stays locked while the counter is loaded, so that concurrent runs can not hand out the same number.
+pub struct Counter {
+    file: File,
+    last: u32,
+    changed: bool,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct State {
+    #[serde(default)]
+    last: u32,
+}
+
+impl Counter {
+    /// Loads and locks the counter, which starts at zero if no roll has been numbered yet.
+    pub fn load() -> Result<Self> {
+        let path = config_dir()
+            .map(|dir| dir.join("rolls.toml"))
+            .ok_or_else(|| anyhow!("No config directory for keeping the roll counter"))?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&path)
+            .with_context(|| format!("Failed to open roll counter {}", path.display()))?;
+        match file.try_lock() {
+            Ok(()) => {}
+            Err(TryLockError::WouldBlock) => {
+                eprintln!("Waiting for another run to finish numbering rolls");
+                file.lock()?;
+            }
+            Err(TryLockError::Error(err)) => return Err(err.into()),
+        }
+
+        let mut content = String::new();
+        file.read_to_string(&mut content)?;
+        let state: State = toml::from_str(&content)
+            .with_context(|| format!("Failed to parse roll counter {}", path.display()))?;
+        Ok(Self {
+            file,
+            last: state.last,
+            changed: false,
+        })
+    }
+
+    /// Takes the next roll number.
+    pub fn next(&mut self) -> String {
+        self.last += 1;
+        self.changed = true;
+        self.last.to_string()
+    }
+
+    /// Moves the counter past a roll that was numbered by hand, if it is the next roll in the sequence.
+    /// Other identifiers, like years or numbers from another system, leave the counter as it is.
+    pub fn note(&mut self, id: &str) {
+        if id.parse::<u32>().ok() == self.last.checked_add(1) {
+            self.last += 1;
+            self.changed = true;
+        }
+    }
+
+    /// Writes the counter back if any roll was numbered.
+    pub fn save(mut self) -> Result<()> {
+        if !self.changed {
+            return Ok(());
+        }
+
+        let content = toml::to_string(&State { last: self.last })?;
+        self.file.set_len(0)?;
+        self.file.seek(SeekFrom::Start(0))?;
+        self.file.write_all(content.as_bytes())?;
+        Ok(())
+    }
+}
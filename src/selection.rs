@@ -0,0 +1,94 @@
+use crate::paths::file_name;
+use crate::sequence::natural_cmp;
+use anyhow::{Context, Result, anyhow};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Extensions of the image formats that Exiv2 can write metadata to.
+const SUPPORTED: &[&str] = &[
+    "jpg", "jpeg", "tif", "tiff", "png", "webp", "heic", "heif", "avif", "jp2", "dng", "arw",
+    "cr2", "cr3", "nef", "orf", "pef", "raf", "rw2", "srw",
+];
+
+/// Options for selecting the files to work on, shared by all subcommands.
+#[derive(clap::Args)]
+pub struct Selection {
+    /// Image files or directories of images to work on.
+    paths: Vec<PathBuf>,
+
+    /// Include images in subdirectories of the given directories.
+    #[arg(short, long)]
+    recursive: bool,
+
+    /// Only include images with these extensions from directories, separated by commas,
+    /// like `jpg,tif`. Defaults to all image formats that can be tagged.
+    #[arg(long, value_delimiter = ',')]
+    ext: Vec<String>,
+}
+
+impl Selection {
+    /// Resolves the selected paths into image files, with the images in each directory in filename order.
+    /// Files given explicitly are always included, while hidden files, sidecars and files
+    /// in unsupported formats are skipped in directories.
+    pub fn files(&self) -> Result<Vec<PathBuf>> {
+        if self.paths.is_empty() {
+            return Err(anyhow!("No files were provided"));
+        }
+
+        let mut files = Vec::new();
+        for path in &self.paths {
+            if path.is_dir() {
+                self.walk(path, &mut files)
+                    .with_context(|| format!("Failed to read directory {}", path.display()))?;
+            } else {
+                files.push(path.clone());
+            }
+        }
+
+        if files.is_empty() {
+            return Err(anyhow!("No images were found in the given directories"));
+        }
+        Ok(files)
+    }
+
+    fn walk(&self, dir: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+        let mut entries = fs::read_dir(dir)?
+            .map(|entry| entry.and_then(|entry| Ok((entry.path(), entry.file_type()?))))
+            .collect::<Result<Vec<_>, _>>()?;
+        entries.sort_by(|(a, _), (b, _)| natural_cmp(&file_name(a), &file_name(b)));
+
+        for (entry, file_type) in entries {
+            if file_name(&entry).starts_with('.') {
+                continue;
+            }
+
+            // Symlinked directories are not followed, as they may link back to a parent and never end.
+            if file_type.is_dir() {
+                if self.recursive {
+                    self.walk(&entry, files)?;
+                }
+            } else if !entry.is_dir() && self.includes(&entry) {
+                files.push(entry);
+            }
+        }
+
+        Ok(())
+    }
+
+    // Sidecars are never matched since `xmp` is not a supported image format.
+    fn includes(&self, file: &Path) -> bool {
+        let Some(extension) = file.extension().and_then(|extension| extension.to_str()) else {
+            return false;
+        };
+
+        if self.ext.is_empty() {
+            SUPPORTED
+                .iter()
+                .any(|supported| extension.eq_ignore_ascii_case(supported))
+        } else {
+            self.ext
+                .iter()
+                .any(|wanted| extension.eq_ignore_ascii_case(wanted.trim().trim_start_matches('.')))
+        }
+    }
+}
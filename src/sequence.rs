@@ -0,0 +1,143 @@
+use crate::paths::file_name;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// The position of a file among the files from the same directory, which is assumed to hold one roll.
+#[derive(Clone, Copy, Debug)]
+pub struct Position {
+    /// Zero-based index of the file in natural filename order.
+    pub index: usize,
+    /// The number of files from the same directory.
+    pub count: usize,
+}
+
+impl Position {
+    /// The index padded to the same width for every file in the roll, so that it
+    /// sorts correctly when used as the fractional seconds of a timestamp.
+    pub fn sub_seconds(self) -> String {
+        let width = self.count.saturating_sub(1).to_string().len().max(3);
+        format!("{:0width$}", self.index)
+    }
+}
+
+/// Finds the position of each file within its directory.
+pub fn positions(files: &[PathBuf]) -> Vec<Position> {
+    let mut rolls: HashMap<&Path, Vec<usize>> = HashMap::new();
+    for (i, file) in files.iter().enumerate() {
+        rolls
+            .entry(file.parent().unwrap_or(file))
+            .or_default()
+            .push(i);
+    }
+
+    let mut positions = vec![Position { index: 0, count: 0 }; files.len()];
+    for mut roll in rolls.into_values() {
+        roll.sort_by(|&a, &b| natural_cmp(&file_name(&files[a]), &file_name(&files[b])));
+        for (index, &i) in roll.iter().enumerate() {
+            positions[i] = Position {
+                index,
+                count: roll.len(),
+            };
+        }
+    }
+
+    positions
+}
+
+/// Compares names with runs of digits compared by their numeric value, so that `scan-2` sorts before `scan-10`.
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let mut a = a.chars().peekable();
+    let mut b = b.chars().peekable();
+
+    loop {
+        match (a.peek().copied(), b.peek().copied()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) if x.is_ascii_digit() && y.is_ascii_digit() => {
+                let x = take_number(&mut a);
+                let y = take_number(&mut b);
+                // Compare by length first to support numbers of any size, ignoring leading zeros.
+                let x = x.trim_start_matches('0');
+                let y = y.trim_start_matches('0');
+                let ordering = x.len().cmp(&y.len()).then_with(|| x.cmp(y));
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+            }
+            (Some(x), Some(y)) => {
+                let ordering = x.cmp(&y);
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+                a.next();
+                b.next();
+            }
+        }
+    }
+}
+
+fn take_number(chars: &mut std::iter::Peekable<std::str::Chars<'_>>) -> String {
+    let mut number = String::new();
+    while let Some(c) = chars.next_if(char::is_ascii_digit) {
+        number.push(c);
+    }
+    number
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compares_numbers_by_value() {
+        assert_eq!(natural_cmp("scan-2", "scan-10"), Ordering::Less);
+        assert_eq!(natural_cmp("scan-10", "scan-2"), Ordering::Greater);
+        assert_eq!(natural_cmp("scan-9.jpg", "scan-10.jpg"), Ordering::Less);
+        assert_eq!(natural_cmp("a", "b"), Ordering::Less);
+        assert_eq!(natural_cmp("scan", "scan-1"), Ordering::Less);
+        assert_eq!(natural_cmp("scan-1", "scan-1"), Ordering::Equal);
+        assert_eq!(
+            natural_cmp("scan-99999999999999999999", "scan-100000000000000000000"),
+            Ordering::Less
+        );
+    }
+
+    #[test]
+    fn ignores_leading_zeros() {
+        assert_eq!(natural_cmp("scan-007", "scan-7"), Ordering::Equal);
+        assert_eq!(natural_cmp("scan-007", "scan-10"), Ordering::Less);
+        assert_eq!(natural_cmp("scan-0010", "scan-9"), Ordering::Greater);
+        assert_eq!(natural_cmp("scan-0", "scan-00"), Ordering::Equal);
+    }
+
+    #[test]
+    fn positions_files_within_their_directory() {
+        let files: Vec<PathBuf> = [
+            "a/scan-10.jpg",
+            "b/scan-1.jpg",
+            "a/scan-2.jpg",
+            "a/scan-1.jpg",
+        ]
+        .into_iter()
+        .map(PathBuf::from)
+        .collect();
+        let positions: Vec<(usize, usize)> = positions(&files)
+            .into_iter()
+            .map(|position| (position.index, position.count))
+            .collect();
+        assert_eq!(positions, [(2, 3), (0, 1), (1, 3), (0, 3)]);
+    }
+
+    #[test]
+    fn pads_sub_seconds_to_the_same_width() {
+        let sub_seconds = |index, count| Position { index, count }.sub_seconds();
+        assert_eq!(sub_seconds(0, 36), "000");
+        assert_eq!(sub_seconds(35, 36), "035");
+        assert_eq!(sub_seconds(999, 1000), "999");
+        assert_eq!(sub_seconds(0, 1001), "0000");
+        assert_eq!(sub_seconds(1000, 1001), "1000");
+        assert_eq!(sub_seconds(42, 12000), "00042");
+    }
+}
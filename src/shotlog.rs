@@ -0,0 +1,114 @@
+use crate::date::parse_date_time;
+use crate::exposure::{Aperture, ExposureTime};
+use crate::location::Coordinates;
+use anyhow::{Context, Result, anyhow};
+use serde::Deserialize;
+use std::path::Path;
+use time::PrimitiveDateTime;
+
+/// A single row as it is written in the CSV file.
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct Row {
+    frame: Option<u32>,
+    file: Option<String>,
+    aperture: Option<String>,
+    shutter: Option<String>,
+    date: Option<String>,
+    notes: Option<String>,
+    location: Option<String>,
+}
+
+/// Metadata noted down for a single frame while shooting.
+#[derive(Default)]
+pub struct Shot {
+    pub aperture: Option<Aperture>,
+    pub shutter: Option<ExposureTime>,
+    pub date: Option<PrimitiveDateTime>,
+    pub notes: Option<String>,
+    /// The name of the place the frame was shot at.
+    pub location: Option<String>,
+    pub coordinates: Option<Coordinates>,
+}
+
+struct Entry {
+    frame: Option<u32>,
+    file: Option<String>,
+    shot: Shot,
+}
+
+/// A log of shots from a roll, read from a CSV file with a header row.
+/// Recognised columns are `frame`, `file`, `aperture`, `shutter`, `date`, `notes` and `location`.
+pub struct ShotLog {
+    entries: Vec<Entry>,
+}
+
+impl ShotLog {
+    pub fn from_path(path: &Path) -> Result<Self> {
+        let mut reader = csv::ReaderBuilder::new()
+            .trim(csv::Trim::All)
+            .from_path(path)
+            .with_context(|| format!("Failed to open shot log {}", path.display()))?;
+
+        let mut entries = Vec::new();
+        for (index, row) in reader.deserialize::<Row>().enumerate() {
+            let entry = row
+                .map_err(anyhow::Error::from)
+                .and_then(Entry::try_from)
+                .with_context(|| {
+                    format!("Invalid row {} in shot log {}", index + 1, path.display())
+                })?;
+            entries.push(entry);
+        }
+
+        Ok(Self { entries })
+    }
+
+    /// Finds the shot for a file, preferring a match on the file name over one on the frame number.
+    pub fn find(&self, file: &Path, frame: Option<u32>) -> Option<&Shot> {
+        let by_name = self.entries.iter().find(|entry| {
+            entry.file.as_deref().is_some_and(|name| {
+                Path::new(name).file_name() == file.file_name()
+                    || file.file_stem() == Some(name.as_ref())
+            })
+        });
+
+        by_name
+            .or_else(|| {
+                let frame = frame?;
+                self.entries
+                    .iter()
+                    .find(|entry| entry.file.is_none() && entry.frame == Some(frame))
+            })
+            .map(|entry| &entry.shot)
+    }
+}
+
+impl TryFrom<Row> for Entry {
+    type Error = anyhow::Error;
+
+    fn try_from(row: Row) -> Result<Self> {
+        if row.frame.is_none() && row.file.is_none() {
+            return Err(anyhow!("Either a frame number or a file name is required"));
+        }
+
+        // The location is either written as coordinates or as the name of a place.
+        let (location, coordinates) = match row.location {
+            Some(location) if Coordinates::is_numeric(&location) => (None, Some(location.parse()?)),
+            location => (location, None),
+        };
+
+        Ok(Self {
+            frame: row.frame,
+            file: row.file,
+            shot: Shot {
+                aperture: row.aperture.as_deref().map(str::parse).transpose()?,
+                shutter: row.shutter.as_deref().map(str::parse).transpose()?,
+                date: row.date.as_deref().map(parse_date_time).transpose()?,
+                notes: row.notes,
+                location,
+                coordinates,
+            },
+        })
+    }
+}
@@ -0,0 +1,123 @@
+use crate::paths::file_name;
+use crate::summary::{Summary, decimal};
+use anyhow::Result;
+use std::collections::HashMap;
+use std::io::{self, IsTerminal};
+use std::path::{Path, PathBuf};
+
+const HEADERS: [&str; 10] = [
+    "File", "Roll", "Film", "ISO", "Camera", "Lens", "Focal", "Captured", "Artist", "GPS",
+];
+
+type Row = [String; HEADERS.len()];
+
+/// Prints the film-relevant metadata of the files as a table, with one row per file.
+/// Columns that are empty for every file are left out, and values that differ from
+/// the most common one in their column are highlighted to make mistakes stand out.
+pub fn show(files: &[PathBuf]) -> Result<()> {
+    let rows = files
+        .iter()
+        .map(|file| Ok(row(file, Summary::read(file)?)))
+        .collect::<Result<Vec<Row>>>()?;
+
+    let columns: Vec<usize> = (0..HEADERS.len())
+        .filter(|&column| column == 0 || rows.iter().any(|row| !row[column].is_empty()))
+        .collect();
+    let common: Vec<Option<&str>> = columns
+        .iter()
+        .map(|&column| most_common(&rows, column))
+        .collect();
+
+    // Without colors, differing values are marked with an asterisk instead.
+    let color = io::stdout().is_terminal();
+    let mut lines = vec![(
+        columns.iter().map(|&c| HEADERS[c].to_string()).collect(),
+        vec![false; columns.len()],
+    )];
+    for row in &rows {
+        // The file name is always unique, so only the other columns are compared.
+        let (cells, differing): (Vec<String>, Vec<bool>) = columns
+            .iter()
+            .zip(&common)
+            .map(|(&column, common)| {
+                let cell = &row[column];
+                let differs = column > 0 && common.is_some_and(|common| common != cell);
+                if differs && !color {
+                    (format!("*{cell}"), false)
+                } else {
+                    (cell.clone(), differs)
+                }
+            })
+            .unzip();
+        lines.push((cells, differing));
+    }
+
+    let widths: Vec<usize> = (0..columns.len())
+        .map(|i| {
+            lines
+                .iter()
+                .map(|(cells, _)| cells[i].chars().count())
+                .max()
+                .unwrap_or_default()
+        })
+        .collect();
+    for (cells, highlighted) in &lines {
+        println!("{}", format_line(cells, &widths, highlighted));
+    }
+
+    Ok(())
+}
+
+fn row(path: &Path, summary: Summary) -> Row {
+    let captured = summary
+        .captured
+        .map(|captured| captured + summary.offset.as_deref().unwrap_or_default());
+
+    [
+        Some(file_name(path)),
+        summary.roll,
+        summary.film,
+        summary.iso,
+        summary.camera,
+        summary.lens,
+        summary
+            .focal_length
+            .map(|focal_length| format!("{}mm", decimal(focal_length))),
+        captured,
+        summary.artist,
+        summary
+            .coordinates
+            .map(|coordinates| coordinates.to_string()),
+    ]
+    .map(Option::unwrap_or_default)
+}
+
+fn most_common(rows: &[Row], column: usize) -> Option<&str> {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for row in rows {
+        *counts.entry(&row[column]).or_default() += 1;
+    }
+
+    // Ties are broken by the value itself to keep the highlighting stable between runs.
+    counts
+        .into_iter()
+        .max_by(|(a, a_count), (b, b_count)| a_count.cmp(b_count).then(b.cmp(a)))
+        .map(|(value, _)| value)
+}
+
+fn format_line(cells: &[String], widths: &[usize], highlighted: &[bool]) -> String {
+    let cells: Vec<String> = cells
+        .iter()
+        .zip(widths)
+        .zip(highlighted)
+        .map(|((cell, &width), &highlighted)| {
+            let padded = format!("{cell:width$}");
+            if highlighted {
+                format!("\x1b[1;33m{padded}\x1b[0m")
+            } else {
+                padded
+            }
+        })
+        .collect();
+    cells.join("  ").trim_end().to_string()
+}
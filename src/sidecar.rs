@@ -0,0 +1,47 @@
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use rexiv2::Metadata;
+use std::path::{Path, PathBuf};
+
+/// The contents of a new sidecar, before any tags have been added to it.
+pub const EMPTY_PACKET: &str = concat!(
+    r#"<?xpacket begin="" id="W5M0MpCehiHzreSzNTczkc9d"?>"#,
+    r#"<x:xmpmeta xmlns:x="adobe:ns:meta/">"#,
+    r#"<rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#"/>"#,
+    r#"</x:xmpmeta>"#,
+    r#"<?xpacket end="w"?>"#,
+);
+
+/// How sidecar files are named after the image they belong to.
+#[derive(Clone, Copy, ValueEnum)]
+pub enum Naming {
+    /// Append `.xmp` to the file name, like `photo.ARW.xmp`, as darktable does.
+    Append,
+    /// Replace the extension with `.xmp`, like `photo.xmp`, as Lightroom does.
+    Replace,
+}
+
+impl Naming {
+    /// The path of the sidecar for the image.
+    pub fn path(self, file: &Path) -> PathBuf {
+        match self {
+            Self::Append => {
+                let mut name = file.as_os_str().to_owned();
+                name.push(".xmp");
+                PathBuf::from(name)
+            }
+            Self::Replace => file.with_extension("xmp"),
+        }
+    }
+}
+
+/// Opens the sidecar at the path to merge new tags into it, or an empty one if it does not exist yet.
+/// Exif tags set on a sidecar are converted to their XMP equivalents by Exiv2 when it is saved.
+pub fn open(path: &Path) -> Result<Metadata> {
+    if path.exists() {
+        Metadata::new_from_path(path)
+            .with_context(|| format!("Failed to read sidecar {}", path.display()))
+    } else {
+        Ok(Metadata::new_from_buffer(EMPTY_PACKET.as_bytes())?)
+    }
+}
@@ -0,0 +1,128 @@
+use crate::location::Coordinates;
+use crate::sidecar::Naming;
+use anyhow::{Context, Result};
+use num_rational::Ratio;
+use rexiv2::Metadata;
+use std::path::Path;
+
+/// The metadata of a scan that matters for film photography, as read back from its tags.
+pub struct Summary {
+    pub roll: Option<String>,
+    pub film: Option<String>,
+    pub iso: Option<String>,
+    pub camera: Option<String>,
+    pub lens: Option<String>,
+    pub focal_length: Option<Ratio<i32>>,
+    pub aperture: Option<Ratio<i32>>,
+    pub shutter: Option<Ratio<i32>>,
+    /// The capture date formatted like `2024-05-12 14:30:00`.
+    pub captured: Option<String>,
+    /// The offset of the capture date from UTC, like `+02:00`.
+    pub offset: Option<String>,
+    pub artist: Option<String>,
+    pub copyright: Option<String>,
+    pub coordinates: Option<Coordinates>,
+    /// The name of the place, as written from the shot log.
+    pub location: Option<String>,
+    pub notes: Option<String>,
+}
+
+impl Summary {
+    /// Reads the metadata of an image, merged with its XMP sidecar when it has one,
+    /// as the sidecar holds what was tagged when the image itself was left untouched.
+    pub fn read(path: &Path) -> Result<Self> {
+        let image = Metadata::new_from_path(path)
+            .with_context(|| format!("Failed to read metadata from {}", path.display()))?;
+        let sidecar = [Naming::Append, Naming::Replace]
+            .into_iter()
+            .map(|naming| naming.path(path))
+            .find(|sidecar| sidecar.is_file())
+            .map(|sidecar| {
+                Metadata::new_from_path(&sidecar)
+                    .with_context(|| format!("Failed to read sidecar {}", sidecar.display()))
+            })
+            .transpose()?;
+        let metas: Vec<&Metadata> = sidecar.iter().chain([&image]).collect();
+        let tag = |name: &str| metas.iter().find_map(|meta| tag(meta, name));
+        let rational = |name: &str| metas.iter().find_map(|meta| rational(meta, name));
+
+        // Earlier versions only wrote the film to the description.
+        let stock = match (tag("Xmp.AnalogExif.FilmMaker"), tag("Xmp.AnalogExif.Film")) {
+            (Some(maker), Some(name)) => Some(format!("{maker} {name}")),
+            (_, Some(name)) => Some(name),
+            _ => tag("Exif.Image.ImageDescription"),
+        };
+
+        // Focal lengths were written to the Image IFD by earlier versions, instead of the Photo IFD.
+        let focal_length =
+            rational("Exif.Photo.FocalLength").or_else(|| rational("Exif.Image.FocalLength"));
+
+        let captured = tag("Exif.Photo.DateTimeOriginal").and_then(|original| {
+            let (date, time) = original.split_once(' ')?;
+            Some(format!("{} {time}", date.replace(':', "-")))
+        });
+
+        let coordinates = metas.iter().find_map(|meta| {
+            let gps = meta.get_gps_info()?;
+            Some(Coordinates {
+                latitude: gps.latitude,
+                longitude: gps.longitude,
+                altitude: meta
+                    .has_tag("Exif.GPSInfo.GPSAltitude")
+                    .then_some(gps.altitude),
+            })
+        });
+
+        Ok(Self {
+            roll: tag("Xmp.AnalogExif.RollId"),
+            film: stock,
+            iso: tag("Exif.Photo.ISOSpeedRatings"),
+            camera: joined(tag("Exif.Image.Make"), tag("Exif.Image.Model")),
+            lens: joined(tag("Exif.Photo.LensMake"), tag("Exif.Photo.LensModel")),
+            focal_length,
+            aperture: rational("Exif.Photo.FNumber"),
+            shutter: rational("Exif.Photo.ExposureTime"),
+            captured,
+            offset: tag("Exif.Photo.OffsetTimeOriginal"),
+            artist: tag("Exif.Image.Artist"),
+            copyright: tag("Exif.Image.Copyright"),
+            coordinates,
+            location: tag("Xmp.iptc.Location"),
+            // The plain value of a comment starts with its character set.
+            notes: metas.iter().find_map(|meta| {
+                let notes = meta
+                    .get_tag_interpreted_string("Exif.Photo.UserComment")
+                    .ok()?;
+                let notes = notes.trim();
+                (!notes.is_empty()).then(|| notes.to_string())
+            }),
+        })
+    }
+}
+
+/// Formats a rational as a decimal number with at most one decimal, like `50` or `5.6`.
+pub fn decimal(value: Ratio<i32>) -> String {
+    let value = f64::from(*value.numer()) / f64::from(*value.denom());
+    let formatted = format!("{value:.1}");
+    formatted.trim_end_matches(".0").to_string()
+}
+
+fn tag(meta: &Metadata, name: &str) -> Option<String> {
+    let value = meta.get_tag_string(name).ok()?;
+    let value = value.trim();
+    (!value.is_empty()).then(|| value.to_string())
+}
+
+fn rational(meta: &Metadata, name: &str) -> Option<Ratio<i32>> {
+    meta.get_tag_rational(name)
+        .filter(|value| *value.denom() != 0)
+}
+
+// Models often repeat the make, like `Canon Canon AE-1`, which is only shown once.
+fn joined(make: Option<String>, model: Option<String>) -> Option<String> {
+    match (make, model) {
+        (Some(make), Some(model)) if !model.starts_with(&make) => Some(format!("{make} {model}")),
+        (_, Some(model)) => Some(model),
+        (make, None) => make,
+    }
+}
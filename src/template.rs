@@ -0,0 +1,150 @@
+use anyhow::{Result, anyhow};
+use clap::ValueEnum;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+/// The values that placeholders are replaced with, by placeholder name.
+pub type Values = BTreeMap<&'static str, String>;
+
+/// The placeholders that can be used in templates.
+pub const PLACEHOLDERS: &[&str] = &[
+    "year",
+    "date",
+    "artist",
+    "film",
+    "iso",
+    "developer",
+    "camera",
+    "lens",
+    "focal",
+    "aperture",
+    "shutter",
+    "format",
+    "roll",
+    "frame",
+    "frames",
+];
+
+/// How an expanded template is combined with the value that a tag already has.
+#[derive(Clone, Copy, Default, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Mode {
+    /// Add the text after the existing value.
+    #[default]
+    Append,
+    /// Add the text before the existing value.
+    Prepend,
+    /// Replace the existing value.
+    Replace,
+}
+
+impl Mode {
+    /// Combines the text with the existing value. Text that is already part of the value
+    /// is not added again, so that tagging the same files twice gives the same result.
+    pub fn apply(self, existing: Option<&str>, text: &str) -> String {
+        let existing = existing
+            .map(str::trim)
+            .filter(|existing| !existing.is_empty());
+        match (self, existing) {
+            (Self::Replace, _) | (_, None) => text.to_string(),
+            (_, Some(existing)) if existing.contains(text) => existing.to_string(),
+            (Self::Append, Some(existing)) => format!("{existing}; {text}"),
+            (Self::Prepend, Some(existing)) => format!("{text}; {existing}"),
+        }
+    }
+}
+
+enum Part<'a> {
+    Text(&'a str),
+    Placeholder(&'a str),
+}
+
+/// Replaces the `{name}` placeholders in the template with their values.
+/// Literal braces are written as `{{` and `}}`.
+pub fn expand(template: &str, values: &Values) -> Result<String> {
+    let mut expanded = String::new();
+    for part in parse(template)? {
+        match part {
+            Part::Text(text) => expanded.push_str(text),
+            Part::Placeholder(name) => {
+                let value = values
+                    .get(name)
+                    .ok_or_else(|| anyhow!("No value for {{{name}}} in template: {template}"))?;
+                expanded.push_str(value);
+            }
+        }
+    }
+    Ok(expanded)
+}
+
+fn parse(template: &str) -> Result<Vec<Part<'_>>> {
+    let mut parts = Vec::new();
+    let mut rest = template;
+    while let Some(i) = rest.find(['{', '}']) {
+        parts.push(Part::Text(&rest[..i]));
+        let tail = &rest[i..];
+        if tail.starts_with("{{") || tail.starts_with("}}") {
+            parts.push(Part::Text(&tail[..1]));
+            rest = &tail[2..];
+        } else if tail.starts_with('}') {
+            return Err(anyhow!("Unmatched }} in template: {template}"));
+        } else {
+            let end = tail
+                .find('}')
+                .ok_or_else(|| anyhow!("Unclosed {{ in template: {template}"))?;
+            let name = tail[1..end].trim();
+            if !PLACEHOLDERS.contains(&name) {
+                return Err(anyhow!(
+                    "Unknown placeholder {{{name}}} in template: {template}, expected one of {}",
+                    PLACEHOLDERS.join(", ")
+                ));
+            }
+            parts.push(Part::Placeholder(name));
+            rest = &tail[end + 1..];
+        }
+    }
+    parts.push(Part::Text(rest));
+    Ok(parts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values() -> Values {
+        Values::from([
+            ("year", "2024".to_string()),
+            ("artist", "Jane Doe".to_string()),
+        ])
+    }
+
+    #[test]
+    fn expands_placeholders() {
+        let expand = |template| expand(template, &values()).unwrap();
+        assert_eq!(expand("© {year} {artist}"), "© 2024 Jane Doe");
+        assert_eq!(expand("{ year }"), "2024");
+        assert_eq!(expand("No placeholders"), "No placeholders");
+        assert_eq!(expand(""), "");
+    }
+
+    #[test]
+    fn escapes_braces() {
+        let expand = |template| expand(template, &values()).unwrap();
+        assert_eq!(expand("{{year}}"), "{year}");
+        assert_eq!(expand("{{{year}}}"), "{2024}");
+        assert_eq!(expand("}}{{"), "}{");
+    }
+
+    #[test]
+    fn rejects_invalid_templates() {
+        for template in ["{year", "year}", "{", "}", "{{year}", "{unknown}", "{}"] {
+            assert!(parse(template).is_err(), "{template}");
+        }
+    }
+
+    #[test]
+    fn rejects_placeholders_without_values() {
+        assert!(parse("{film}").is_ok());
+        assert!(expand("{film}", &values()).is_err());
+    }
+}
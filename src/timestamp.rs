@@ -0,0 +1,148 @@
+use crate::zone::Zone;
+use anyhow::Result;
+use rexiv2::Metadata;
+use std::path::Path;
+use time::format_description::FormatItem;
+use time::format_description::well_known::Rfc3339;
+use time::{OffsetDateTime, PrimitiveDateTime, UtcOffset, macros::format_description};
+
+const DATE_TIME_FORMAT: &[FormatItem<'_>] =
+    format_description!("[year]:[month]:[day] [hour]:[minute]:[second]");
+
+const OFFSET_FORMAT: &[FormatItem<'_>] =
+    format_description!("[offset_hour sign:mandatory]:[offset_minute]");
+
+/// A timestamp tag together with the tag holding its offset from UTC.
+type Stamp = (&'static str, &'static str);
+
+const ORIGINAL: Stamp = (
+    "Exif.Photo.DateTimeOriginal",
+    "Exif.Photo.OffsetTimeOriginal",
+);
+const DIGITIZED: Stamp = (
+    "Exif.Photo.DateTimeDigitized",
+    "Exif.Photo.OffsetTimeDigitized",
+);
+const MODIFIED: Stamp = ("Exif.Image.DateTime", "Exif.Photo.OffsetTime");
+
+/// The scan date, kept when the digitized date is set to the capture date.
+const SCAN_DATE: &str = "Xmp.rolltag.ScanDate";
+
+const ROLLTAG_NAMESPACE: &str = "https://github.com/Jacalz/rolltag/ns/1.0/";
+
+/// Registers the namespace of the tags that only rolltag writes, which has to be done before any of them are written.
+pub fn register_namespace() -> Result<()> {
+    rexiv2::register_xmp_namespace(ROLLTAG_NAMESPACE, "rolltag")?;
+    Ok(())
+}
+
+/// The capture date of a frame, which is only estimated when it stands in for an unknown one.
+#[derive(Clone, Copy)]
+pub struct Captured {
+    pub time: OffsetDateTime,
+    /// Whether the scan date was used in place of a capture date that is not known.
+    pub estimated: bool,
+}
+
+/// Writes both the capture date, when the frame was shot, and the digitized date, when it was scanned.
+/// Returns the capture date that was written.
+///
+/// Existing timestamps are read from `meta` and then from `source`, which differ when writing to a sidecar.
+///
+/// The scan date is taken from what the scanner wrote, or else the modification time of the file.
+/// Without a known capture date, the existing one is kept, or else the scan date is used in its place.
+/// This is required to ensure correct ordering when sorting files to avoid
+/// using the modification date as the primary sorting key. Existing capture dates that
+/// can not be read are left as they are.
+///
+/// When the digitized date is set to the capture date, the scan date is kept in `Xmp.rolltag.ScanDate`
+/// so that it is not lost when tagging the files again.
+pub fn set_timestamps(
+    file: &Path,
+    source: &Metadata,
+    meta: &Metadata,
+    capture: Option<PrimitiveDateTime>,
+    zone: Zone,
+    digitized_as_original: bool,
+) -> Result<Captured> {
+    // Scans are made where the scanner is, which is not necessarily where the roll was shot.
+    let local = Zone::local();
+    let existing = |stamp, zone| read(meta, stamp, zone).or_else(|| read(source, stamp, zone));
+    let kept = read_scan_date(meta).or_else(|| read_scan_date(source));
+    let scanned = match kept
+        .or_else(|| existing(DIGITIZED, local))
+        .or_else(|| existing(MODIFIED, local))
+    {
+        Some(scanned) => scanned,
+        None => local.convert(OffsetDateTime::from(file.metadata()?.modified()?)),
+    };
+
+    let captured = if let Some(capture) = capture {
+        let time = zone.assume(capture);
+        write(meta, ORIGINAL, time)?;
+        Captured {
+            time,
+            estimated: false,
+        }
+    } else if let Some(original) = existing(ORIGINAL, zone) {
+        // Capture dates written in place of an unknown one by earlier runs are still only the scan date.
+        write(meta, ORIGINAL, original)?;
+        Captured {
+            time: original,
+            estimated: original == scanned,
+        }
+    } else {
+        if let Some(unreadable) = unreadable(meta, source) {
+            eprintln!(
+                "Keeping the capture date of {} that could not be read: {unreadable}",
+                file.display()
+            );
+        } else {
+            write(meta, ORIGINAL, zone.convert(scanned))?;
+        }
+        Captured {
+            time: zone.convert(scanned),
+            estimated: true,
+        }
+    };
+
+    if digitized_as_original {
+        meta.set_tag_string(SCAN_DATE, &scanned.format(&Rfc3339)?)?;
+        write(meta, DIGITIZED, captured.time)?;
+    } else {
+        write(meta, DIGITIZED, scanned)?;
+    }
+    Ok(captured)
+}
+
+/// Reads the scan date that was kept when the digitized date was set to the capture date.
+fn read_scan_date(meta: &Metadata) -> Option<OffsetDateTime> {
+    let time = meta.get_tag_string(SCAN_DATE).ok()?;
+    OffsetDateTime::parse(time.trim(), &Rfc3339).ok()
+}
+
+/// The existing capture date, if there is one that is not empty but could not be read.
+fn unreadable(meta: &Metadata, source: &Metadata) -> Option<String> {
+    [meta, source].into_iter().find_map(|meta| {
+        let time = meta.get_tag_string(ORIGINAL.0).ok()?;
+        let time = time.trim();
+        (!time.is_empty()).then(|| time.to_string())
+    })
+}
+
+/// Reads a timestamp, using the offset from its offset tag if there is one or else the given zone.
+fn read(meta: &Metadata, (tag, offset_tag): Stamp, zone: Zone) -> Option<OffsetDateTime> {
+    let time = meta.get_tag_string(tag).ok()?;
+    let time = PrimitiveDateTime::parse(time.trim(), DATE_TIME_FORMAT).ok()?;
+    let offset = meta
+        .get_tag_string(offset_tag)
+        .ok()
+        .and_then(|offset| UtcOffset::parse(offset.trim(), OFFSET_FORMAT).ok());
+    Some(offset.map_or_else(|| zone.assume(time), |offset| time.assume_offset(offset)))
+}
+
+fn write(meta: &Metadata, (tag, offset_tag): Stamp, time: OffsetDateTime) -> Result<()> {
+    meta.set_tag_string(tag, &time.format(DATE_TIME_FORMAT)?)?;
+    meta.set_tag_string(offset_tag, &time.format(OFFSET_FORMAT)?)?;
+    Ok(())
+}
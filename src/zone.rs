@@ -0,0 +1,91 @@
+use crate::de::deserialize_from_str;
+use anyhow::{Error, Result, anyhow};
+use std::str::FromStr;
+use std::sync::LazyLock;
+use time::{Duration, OffsetDateTime, PrimitiveDateTime, UtcOffset, macros::format_description};
+use time_tz::{Offset, OffsetDateTimeExt, PrimitiveDateTimeExt, TimeZone, Tz, system, timezones};
+
+/// The time zone that a roll was shot in, either a fixed offset from UTC or a named zone.
+#[derive(Clone, Copy)]
+pub enum Zone {
+    Fixed(UtcOffset),
+    Named(&'static Tz),
+}
+
+impl Zone {
+    /// The time zone of the system, falling back to UTC when it can not be determined.
+    pub fn local() -> Self {
+        static LOCAL: LazyLock<Zone> = LazyLock::new(|| {
+            system::get_timezone().map_or(Zone::Fixed(UtcOffset::UTC), Zone::Named)
+        });
+        *LOCAL
+    }
+
+    /// Places a local time in the zone. Times that are skipped or repeated by
+    /// daylight saving time changes use the offset from before the change.
+    pub fn assume(self, time: PrimitiveDateTime) -> OffsetDateTime {
+        match self {
+            Self::Fixed(offset) => time.assume_offset(offset),
+            Self::Named(tz) => time.assume_timezone(tz).take_first().unwrap_or_else(|| {
+                // Changes are months apart, so the offset a day earlier is the one from before this change.
+                let before = tz.get_offset_utc(&(time - Duration::DAY).assume_utc());
+                time.assume_offset(before.to_utc())
+            }),
+        }
+    }
+
+    /// Converts a point in time to the local time in the zone.
+    pub fn convert(self, time: OffsetDateTime) -> OffsetDateTime {
+        match self {
+            Self::Fixed(offset) => time.to_offset(offset),
+            Self::Named(tz) => time.to_timezone(tz),
+        }
+    }
+}
+
+impl FromStr for Zone {
+    type Err = Error;
+
+    /// Parses zones written as offsets like `+02:00`, `-0530` or `Z`, or as names like `Europe/Stockholm`.
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        if s.eq_ignore_ascii_case("z") || s.eq_ignore_ascii_case("utc") {
+            return Ok(Self::Fixed(UtcOffset::UTC));
+        }
+
+        let with_colon = format_description!("[offset_hour sign:mandatory]:[offset_minute]");
+        let without_colon = format_description!("[offset_hour sign:mandatory][offset_minute]");
+        let hours_only = format_description!("[offset_hour sign:mandatory]");
+        if let Some(offset) = [with_colon, without_colon, hours_only]
+            .iter()
+            .find_map(|format| UtcOffset::parse(s, format).ok())
+        {
+            return Ok(Self::Fixed(offset));
+        }
+
+        timezones::get_by_name(s)
+            .map(Self::Named)
+            .ok_or_else(|| anyhow!("Unknown time zone: {s}"))
+    }
+}
+
+deserialize_from_str!(Zone);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::{datetime, offset};
+
+    #[test]
+    fn uses_the_offset_from_before_daylight_saving_changes() {
+        let zone: Zone = "Europe/Stockholm".parse().unwrap();
+        // Clocks went forward from 02:00 to 03:00 and back from 03:00 to 02:00.
+        let skipped = zone.assume(datetime!(2024-03-31 02:30));
+        assert_eq!(skipped, datetime!(2024-03-31 02:30 +01:00));
+        assert_eq!(skipped.offset(), offset!(+01:00));
+        let repeated = zone.assume(datetime!(2024-10-27 02:30));
+        assert_eq!(repeated.offset(), offset!(+02:00));
+        let summer = zone.assume(datetime!(2024-07-01 12:00));
+        assert_eq!(summer.offset(), offset!(+02:00));
+    }
+}